@@ -0,0 +1,5 @@
+-- Settlement outcome mirrored from the on-chain MarketAccount.
+-- VOID = flat candle or one-sided market, every bet is refunded.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS outcome TEXT DEFAULT 'PENDING'
+    CHECK (outcome IN ('PENDING','GREEN','RED','VOID'));
@@ -0,0 +1,4 @@
+-- A wallet holds a single accumulated position per market, mirroring the
+-- on-chain UserBetAccount PDA seeded by [bet, user, market].
+ALTER TABLE bets
+    ADD CONSTRAINT bets_wallet_market_key UNIQUE (wallet, market_id);
@@ -0,0 +1,9 @@
+-- SPL token markets: record the mint each market / bet is denominated in.
+-- A NULL mint means native SOL (9 decimals, amounts in lamports).
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS mint TEXT,
+    ADD COLUMN IF NOT EXISTS decimals SMALLINT NOT NULL DEFAULT 9;
+
+ALTER TABLE bets
+    ADD COLUMN IF NOT EXISTS mint TEXT,
+    ADD COLUMN IF NOT EXISTS decimals SMALLINT NOT NULL DEFAULT 9;
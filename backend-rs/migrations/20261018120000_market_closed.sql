@@ -0,0 +1,3 @@
+-- Set once close_market has reclaimed the on-chain MarketAccount rent.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS closed BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Protocol fee snapshot per market and fees collected so far by claims.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS fee_bps SMALLINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS fees_accrued NUMERIC NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- End of the on-chain dispute window; claims unlock from this time.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS finalizes_at TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- Mirror of the on-chain AssetConfig PDAs ([asset, symbol]). Refreshed on
+-- startup and whenever an asset is registered or updated through /admin.
+CREATE TABLE IF NOT EXISTS assets (
+    symbol TEXT PRIMARY KEY,
+    price_decimals SMALLINT NOT NULL,
+    price_feed TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
@@ -0,0 +1,6 @@
+-- Positions sold back before lock through withdraw_bet. The refund is kept
+-- in payout; the row is reset if the wallet bets on the market again.
+ALTER TABLE bets
+    ADD COLUMN IF NOT EXISTS withdrawn BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS exit_penalty NUMERIC(30,10) NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS withdrawn_at TIMESTAMPTZ;
@@ -0,0 +1,17 @@
+-- Range markets: N price buckets instead of Green/Red. Bets on them use
+-- side 'BUCKET_<n>', and settled ones outcome 'BUCKET_<n>'. Both arrays are
+-- empty for Green/Red markets.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS bucket_bounds INTEGER[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS bucket_pools_weighted NUMERIC[] NOT NULL DEFAULT '{}';
+
+-- Sides and outcomes were limited to Green/Red by earlier CHECKs
+ALTER TABLE bets DROP CONSTRAINT IF EXISTS bets_side_check;
+ALTER TABLE bets
+    ADD CONSTRAINT bets_side_check
+    CHECK (side IN ('GREEN','RED') OR side LIKE 'BUCKET_%');
+
+ALTER TABLE markets DROP CONSTRAINT IF EXISTS markets_outcome_check;
+ALTER TABLE markets
+    ADD CONSTRAINT markets_outcome_check
+    CHECK (outcome IN ('PENDING','GREEN','RED','VOID') OR outcome LIKE 'BUCKET_%');
@@ -0,0 +1,4 @@
+-- Price a market is decided against when it is not its open price, e.g.
+-- the rungs of a ladder of strikes on the same candle. NULL = open price.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS strike_price NUMERIC(30,10);
@@ -0,0 +1,7 @@
+-- Volatility markets resolve on the candle's high - low instead of its
+-- close (NULL threshold for every other market). High and low are recorded
+-- at settlement for all markets.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS volatility_threshold_bps INTEGER,
+    ADD COLUMN IF NOT EXISTS high_price NUMERIC(30,10),
+    ADD COLUMN IF NOT EXISTS low_price NUMERIC(30,10);
@@ -0,0 +1,7 @@
+-- Head-to-head markets pay the side whose asset returned more over the
+-- candle: GREEN for `asset`, RED for `rival_asset` (NULL for every other
+-- market). The rival's close is recorded at settlement.
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS rival_asset TEXT,
+    ADD COLUMN IF NOT EXISTS rival_open_price NUMERIC(30,10),
+    ADD COLUMN IF NOT EXISTS rival_close_price NUMERIC(30,10);
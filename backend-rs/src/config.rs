@@ -6,13 +6,20 @@ pub struct AppConfig {
     pub rpc_url: String,
     pub program_id: String,
     pub admin_keypair: String,
+    /// Shared secret for /admin routes; admin routes are disabled when unset
+    pub admin_api_key: Option<String>,
+    /// SETTLEMENT_MODE=vote: submit close prices to the on-chain settler set
+    /// instead of settling with this node's key alone
+    pub settle_by_vote: bool,
+    /// SETTLER_ONLY=true: this node only votes on close prices; market
+    /// creation and the janitor are left to the primary backend
+    pub settler_only: bool,
+    /// MARKET_ASSET: the registered asset the scheduler opens markets for
+    pub market_asset: String,
     #[allow(dead_code)]
     pub backend_port: u16,
 }
 
-// Single-asset MVP — only BTC/USDT is used everywhere in backend
-pub const MARKET_ASSET: &str = "BTC/USDT";
-
 impl AppConfig {
     pub fn load() -> Self {
         dotenv().ok(); // Load .env variables
@@ -26,6 +33,21 @@ impl AppConfig {
         let admin_keypair = env::var("ADMIN_KEYPAIR")
             .expect("ADMIN_KEYPAIR must be set");
 
+        let admin_api_key = env::var("ADMIN_API_KEY")
+            .ok()
+            .filter(|k| !k.trim().is_empty());
+
+        let settle_by_vote = env::var("SETTLEMENT_MODE")
+            .map(|m| m.eq_ignore_ascii_case("vote"))
+            .unwrap_or(false);
+
+        let settler_only = env::var("SETTLER_ONLY")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        let market_asset = env::var("MARKET_ASSET")
+            .unwrap_or_else(|_| "BTC/USDT".to_string());
+
         let backend_port = env::var("BACKEND_PORT")
             .unwrap_or_else(|_| "3000".to_string())
             .parse::<u16>()
@@ -35,6 +57,10 @@ impl AppConfig {
             rpc_url,
             program_id,
             admin_keypair,
+            admin_api_key,
+            settle_by_vote,
+            settler_only,
+            market_asset,
             backend_port,
         }
     }
@@ -1,16 +1,42 @@
 // ---------------------------------------------------------
 // constants.rs
 // ---------------------------------------------------------
-// This file now defines constants for the single-asset MVP.
-// The entire backend operates only on BTC/USDT markets.
-//
-// All previous multi-asset arrays are removed to simplify
-// the backend and match our single-market architecture.
+// Tradable symbols live on-chain in the AssetConfig registry
+// (mirrored in the `assets` table). The scheduler opens markets
+// for the MARKET_ASSET env var (AppConfig), which must be
+// registered.
 // ---------------------------------------------------------
 
-/// The single supported asset in the MVP.
-pub const MARKET_ASSET: &str = "BTC/USDT";
+/// Buckets of the range market opened next to each Green/Red market, as
+/// bps change from the open: < -2%, -2..0%, 0..2%, > 2%. Leave empty to
+/// only open Green/Red markets.
+pub const RANGE_BUCKET_BOUNDS_BPS: &[i32] = &[-200, 0, 200];
 
-/// The Binance-compatible symbol for BTC/USDT.
-/// Example: "BTCUSDT" for klines, price feeds, etc.
-pub const BINANCE_SYMBOL: &str = "BTCUSDT";
+/// Added to the Green/Red market_id of the same candle to get the range
+/// market's. Candles start on 4h boundaries, so ids never collide.
+pub const RANGE_MARKET_ID_OFFSET: i64 = 1;
+
+/// Strikes of the above/below ladder opened next to each Green/Red
+/// market, as bps away from the open. Leave empty for no ladder.
+pub const LADDER_STRIKE_OFFSETS_BPS: &[i32] = &[-100, 100];
+
+/// Ladder market `i` gets the Green/Red market_id plus this plus `i`.
+pub const LADDER_MARKET_ID_OFFSET: i64 = 2;
+
+/// Thresholds of the volatility markets opened next to each Green/Red
+/// market: GREEN wins if the candle's high - low exceeds this many bps of
+/// the open. Leave empty for none.
+pub const VOLATILITY_THRESHOLDS_BPS: &[u32] = &[300];
+
+/// Volatility market `i` gets the Green/Red market_id plus this plus `i`.
+/// Leaves room for ladders of up to 8 strikes.
+pub const VOLATILITY_MARKET_ID_OFFSET: i64 = 10;
+
+/// Rivals of the head-to-head markets opened next to each Green/Red
+/// market: GREEN wins if the market asset returns more than the rival over
+/// the candle, RED if it returns less. Each must be registered. Leave
+/// empty for none.
+pub const HEAD_TO_HEAD_RIVALS: &[&str] = &["ETH/USDT"];
+
+/// Head-to-head market `i` gets the Green/Red market_id plus this plus `i`.
+pub const HEAD_TO_HEAD_MARKET_ID_OFFSET: i64 = 20;
@@ -8,7 +8,7 @@ use backend_rs::db;
 use backend_rs::state::AppState;
 
 // Route modules
-use backend_rs::routes::{market, pnl, oracle, health, claim, treasury, prices};
+use backend_rs::routes::{market, pnl, oracle, health, claim, treasury, prices, admin};
 
 // Axum + CORS
 use axum::{Router, serve};
@@ -40,14 +40,27 @@ async fn main() -> anyhow::Result<()> {
     let state = Arc::new(AppState {
         sol: sol.clone(),
         pool: pool.clone(),
+        admin_api_key: cfg.admin_api_key.clone(),
+        market_asset: cfg.market_asset.clone(),
     });
 
+    // -------------------------------
+    // ASSET REGISTRY
+    // -------------------------------
+    // Mirror the on-chain AssetConfig accounts before anything creates markets
+    if let Err(e) = scheduler::sync_assets(sol.clone(), pool.clone()).await {
+        tracing::warn!("Asset sync failed, using the last known assets: {:?}", e);
+    }
+
     // -------------------------------
     // CREATE INITIAL MARKET ON STARTUP
     // -------------------------------
     // Ensure a market exists immediately after deployment
-    if let Err(e) = scheduler::create_initial_market(sol.clone(), pool.clone()).await {
-        tracing::warn!("Initial market creation failed (may already exist): {:?}", e);
+    // (settler-only nodes leave market creation to the primary backend)
+    if !cfg.settler_only {
+        if let Err(e) = scheduler::create_initial_market(sol.clone(), pool.clone(), &cfg.market_asset).await {
+            tracing::warn!("Initial market creation failed (may already exist): {:?}", e);
+        }
     }
 
     // -------------------------------
@@ -56,9 +69,11 @@ async fn main() -> anyhow::Result<()> {
     tokio::spawn({
         let sol = sol.clone();
         let pool = pool.clone();
+        let market_asset = cfg.market_asset.clone();
+        let settler_only = cfg.settler_only;
         async move {
             tracing::info!("Starting scheduler...");
-            if let Err(e) = scheduler::start_scheduler(sol, pool).await {
+            if let Err(e) = scheduler::start_scheduler(sol, pool, market_asset, settler_only).await {
                 tracing::error!("Scheduler failed: {:?}", e);
             }
         }
@@ -80,6 +95,7 @@ async fn main() -> anyhow::Result<()> {
         .nest("/claim", claim::routes())
         .nest("/treasury", treasury::treasury_routes())
         .nest("/prices", prices::routes())
+        .nest("/admin", admin::routes())
         .with_state(state)
         .layer(cors);
 
@@ -1,14 +1,23 @@
 use anyhow::{anyhow, Result};
+use chrono::Utc;
 use serde_json::Value;
 use reqwest::Client;
 use std::time::Duration as StdDuration;
 
 use crate::oracle::types::CandleData;
-use crate::constants::BINANCE_SYMBOL;
 
-/// Fetch a single Binance candle for BTC/USDT
+/// Binance symbol for an asset: "BTC/USDT", "btc-usdt" → "BTCUSDT"
+pub fn binance_symbol(asset: &str) -> String {
+    asset
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+/// Fetch a single Binance candle for `asset` (e.g. "ETH/USDT")
 /// hours = 1h, 2h, 4h, 6h, 12h, 24h
-pub async fn fetch_binance_candle(hours: i64) -> Result<CandleData> {
+pub async fn fetch_binance_candle(asset: &str, hours: i64) -> Result<CandleData> {
     // Map hours → Binance interval
     let interval = match hours {
         1 => "1h",
@@ -20,10 +29,9 @@ pub async fn fetch_binance_candle(hours: i64) -> Result<CandleData> {
         _ => return Err(anyhow!("Unsupported candle interval: {}h", hours)),
     };
 
-    // We now ALWAYS use "BTCUSDT"
     let url = format!(
         "https://api.binance.com/api/v3/klines?symbol={}&interval={}&limit=1",
-        BINANCE_SYMBOL,
+        binance_symbol(asset),
         interval
     );
 
@@ -56,10 +64,71 @@ pub async fn fetch_binance_candle(hours: i64) -> Result<CandleData> {
     })
 }
 
-/// Fetch historical candles from Binance for BTC/USDT
+/// Fetch the `asset` candle that opened at `start_time` (unix seconds).
+/// Errors if Binance has no candle starting exactly then, or if it is
+/// still open.
+pub async fn fetch_binance_candle_at(asset: &str, hours: i64, start_time: i64) -> Result<CandleData> {
+    let interval = match hours {
+        1 => "1h",
+        2 => "2h",
+        4 => "4h",
+        6 => "6h",
+        12 => "12h",
+        24 => "1d",
+        _ => return Err(anyhow!("Unsupported candle interval: {}h", hours)),
+    };
+
+    let url = format!(
+        "https://api.binance.com/api/v3/klines?symbol={}&interval={}&startTime={}&limit=1",
+        binance_symbol(asset),
+        interval,
+        start_time * 1000
+    );
+
+    let client = Client::builder()
+        .timeout(StdDuration::from_secs(10))
+        .build()?;
+
+    let resp = client.get(&url).send().await?;
+    let json: Value = resp.json().await?;
+
+    let arr = json.as_array()
+        .and_then(|v| v.get(0))
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow!("Invalid Binance candle format"))?;
+
+    // Binance returns the next candle when the requested one is missing
+    let timestamp = arr[0].as_i64().unwrap() / 1000;
+    if timestamp != start_time {
+        return Err(anyhow!("No Binance candle for {} opening at {}", asset, start_time));
+    }
+    let close_time = arr[6].as_i64().unwrap_or(i64::MAX) / 1000;
+    if close_time >= Utc::now().timestamp() {
+        return Err(anyhow!("Binance candle for {} opening at {} is still open", asset, start_time));
+    }
+
+    let open  = arr[1].as_str().unwrap().parse::<f64>()?;
+    let high  = arr[2].as_str().unwrap().parse::<f64>()?;
+    let low   = arr[3].as_str().unwrap().parse::<f64>()?;
+    let close = arr[4].as_str().unwrap().parse::<f64>()?;
+
+    Ok(CandleData {
+        open,
+        high,
+        low,
+        close,
+        timestamp,
+    })
+}
+
+/// Fetch historical candles from Binance for `asset`
 /// hours = 1h, 2h, 4h, 6h, 12h, 24h
 /// limit = number of candles to fetch (max 1000)
-pub async fn fetch_binance_historical(hours: i64, limit: usize) -> Result<Vec<CandleData>> {
+pub async fn fetch_binance_historical(
+    asset: &str,
+    hours: i64,
+    limit: usize,
+) -> Result<Vec<CandleData>> {
     // Map hours → Binance interval
     let interval = match hours {
         1 => "1h",
@@ -76,7 +145,7 @@ pub async fn fetch_binance_historical(hours: i64, limit: usize) -> Result<Vec<Ca
 
     let url = format!(
         "https://api.binance.com/api/v3/klines?symbol={}&interval={}&limit={}",
-        BINANCE_SYMBOL,
+        binance_symbol(asset),
         interval,
         limit
     );
@@ -1,6 +1,6 @@
 // -----------------------------------------------------------------------------
 // oracle/mod.rs
-// Simplified Oracle Module for SINGLE-ASSET MVP (BTC/USDT only)
+// Candle oracle: Binance for any listed asset, Yahoo fallback for BTC
 // -----------------------------------------------------------------------------
 
 pub mod types;
@@ -8,28 +8,60 @@ pub mod tradingview;  // kept as a fallback option
 pub mod binance;
 
 pub use types::CandleData;
-pub use binance::fetch_binance_historical;
+pub use binance::{binance_symbol, fetch_binance_historical};
 
 use anyhow::{Result, anyhow};
 
-/// Fetch latest BTC/USDT candle.
+/// Fetch latest candle for `asset` (e.g. "BTC/USDT").
 /// Primary source: Binance
-/// Fallback: TradingView (Yahoo Finance)
-pub async fn get_latest_candle(hours: i64) -> Result<CandleData> {
-    // 1. Primary oracle: Binance BTCUSDT
-    match crate::oracle::binance::fetch_binance_candle(hours).await {
+/// Fallback: TradingView (Yahoo Finance), BTC only
+pub async fn get_latest_candle(asset: &str, hours: i64) -> Result<CandleData> {
+    // 1. Primary oracle: Binance
+    match crate::oracle::binance::fetch_binance_candle(asset, hours).await {
         Ok(cndl) => return Ok(cndl),
         Err(e) => {
-            tracing::error!("Binance oracle failed: {:?}", e);
+            tracing::error!("Binance oracle failed for {}: {:?}", asset, e);
         }
     }
 
     // 2. Fallback oracle: TradingView/Yahoo BTCUSD
+    if binance_symbol(asset) != "BTCUSDT" {
+        return Err(anyhow!("Binance oracle failed for {} ({}h).", asset, hours));
+    }
+
     match crate::oracle::tradingview::fetch_tradingview_candle(hours).await {
         Ok(cndl) => Ok(cndl),
         Err(_) => Err(anyhow!(
-            "All oracle sources failed for BTC/USDT ({}h).",
+            "All oracle sources failed for {} ({}h).",
+            asset,
             hours
         )),
     }
 }
+
+/// Fetch the candle of `asset` that opened at `start_time` (unix seconds),
+/// for settling the market opened on it. Same sources as get_latest_candle.
+pub async fn get_candle_at(asset: &str, hours: i64, start_time: i64) -> Result<CandleData> {
+    // 1. Primary oracle: Binance
+    match crate::oracle::binance::fetch_binance_candle_at(asset, hours, start_time).await {
+        Ok(cndl) => return Ok(cndl),
+        Err(e) => {
+            tracing::error!("Binance oracle failed for {} at {}: {:?}", asset, start_time, e);
+        }
+    }
+
+    // 2. Fallback oracle: TradingView/Yahoo BTCUSD
+    if binance_symbol(asset) != "BTCUSDT" {
+        return Err(anyhow!("Binance oracle failed for {} ({}h at {}).", asset, hours, start_time));
+    }
+
+    match crate::oracle::tradingview::fetch_tradingview_candle_at(hours, start_time).await {
+        Ok(cndl) => Ok(cndl),
+        Err(_) => Err(anyhow!(
+            "All oracle sources failed for {} ({}h at {}).",
+            asset,
+            hours,
+            start_time
+        )),
+    }
+}
@@ -90,11 +90,19 @@ pub async fn fetch_tradingview_candle(candle_hours: i64) -> Result<CandleData> {
 
     let idx = o.len() - 1;
 
+    // Yahoo leaves null where it has no price; never pass that on as 0
+    let price = |series: &Vec<Value>| {
+        series.get(idx)
+            .and_then(Value::as_f64)
+            .filter(|p| *p > 0.0)
+            .ok_or_else(|| anyhow!("Yahoo bar {} has a missing price", idx))
+    };
+
     let candle = CandleData {
-        open: o[idx].as_f64().unwrap_or(0.0),
-        high: h[idx].as_f64().unwrap_or(0.0),
-        low:  l[idx].as_f64().unwrap_or(0.0),
-        close: c[idx].as_f64().unwrap_or(0.0),
+        open: price(o)?,
+        high: price(h)?,
+        low: price(l)?,
+        close: price(c)?,
         timestamp: timestamps[idx].as_i64().unwrap_or(0),
     };
 
@@ -106,3 +114,83 @@ pub async fn fetch_tradingview_candle(candle_hours: i64) -> Result<CandleData> {
 
     Ok(candle)
 }
+
+/// ----------------------------------------------------------------------------
+/// fetch_tradingview_candle_at()
+/// Fallback oracle for a past BTC candle
+/// ----------------------------------------------------------------------------
+/// Builds the `candle_hours` candle that opened at `start_time` from
+/// Yahoo's hourly bars. Errors unless every hour is there with all four
+/// prices.
+/// ----------------------------------------------------------------------------
+pub async fn fetch_tradingview_candle_at(candle_hours: i64, start_time: i64) -> Result<CandleData> {
+    let end_time = start_time + candle_hours * 3600;
+
+    let url = format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}?interval=60m&period1={}&period2={}",
+        "BTC-USD",
+        start_time,
+        end_time
+    );
+
+    let client = Client::builder()
+        .timeout(StdDuration::from_secs(10))
+        .build()?;
+
+    let resp = client.get(&url).send().await?;
+    if !resp.status().is_success() {
+        return Err(anyhow!("Yahoo Finance returned HTTP {}", resp.status()));
+    }
+
+    let json: Value = resp.json().await?;
+
+    let result = json["chart"]["result"]
+        .as_array()
+        .and_then(|arr| arr.get(0))
+        .ok_or_else(|| anyhow!("Missing chart.result[0] in Yahoo response"))?;
+
+    let timestamps = result["timestamp"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Missing timestamp"))?;
+
+    let indicators = result["indicators"]["quote"]
+        .as_array()
+        .and_then(|arr| arr.get(0))
+        .ok_or_else(|| anyhow!("Missing indicators.quote[0]"))?;
+
+    let o = indicators["open"].as_array().ok_or_else(|| anyhow!("Missing open"))?;
+    let h = indicators["high"].as_array().ok_or_else(|| anyhow!("Missing high"))?;
+    let l = indicators["low"].as_array().ok_or_else(|| anyhow!("Missing low"))?;
+    let c = indicators["close"].as_array().ok_or_else(|| anyhow!("Missing close"))?;
+
+    let bars: Vec<usize> = (0..timestamps.len())
+        .filter(|&i| {
+            let t = timestamps[i].as_i64().unwrap_or(0);
+            t >= start_time && t < end_time
+        })
+        .collect();
+    if bars.len() as i64 != candle_hours {
+        return Err(anyhow!("Yahoo has {} of {} hourly bars", bars.len(), candle_hours));
+    }
+
+    let price = |series: &Vec<Value>, i: usize| {
+        series.get(i)
+            .and_then(Value::as_f64)
+            .filter(|p| *p > 0.0)
+            .ok_or_else(|| anyhow!("Yahoo bar {} has a missing price", i))
+    };
+
+    let mut candle = CandleData {
+        open: price(o, bars[0])?,
+        high: 0.0,
+        low: f64::MAX,
+        close: price(c, bars[bars.len() - 1])?,
+        timestamp: start_time,
+    };
+    for &i in &bars {
+        candle.high = candle.high.max(price(h, i)?);
+        candle.low = candle.low.min(price(l, i)?);
+    }
+
+    Ok(candle)
+}
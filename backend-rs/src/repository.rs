@@ -3,6 +3,11 @@ use sqlx::{Pool, Postgres, Row};
 use chrono::{DateTime, Utc};
 use anyhow::Result;
 use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
+use candle_markets::state::{
+    parimutuel_payout, protocol_fee, resolve_head_to_head_outcome, resolve_outcome,
+    resolve_range_outcome, resolve_volatility_outcome, split_pools, AssetConfig, BetSide,
+    MarketOutcome, MarketParams, MarketTotals,
+};
 
 //
 // Data Models
@@ -17,11 +22,34 @@ pub struct Market {
     pub end_time: DateTime<Utc>,
     pub lock_time: DateTime<Utc>,
     pub open_price: Option<f64>,
+    /// What the close is compared with; None = the open price
+    pub strike_price: Option<f64>,
     pub close_price: Option<f64>,
+    /// Candle high and low, recorded at settlement
+    pub high_price: Option<f64>,
+    pub low_price: Option<f64>,
     pub green_pool_weighted: Option<f64>,
     pub red_pool_weighted: Option<f64>,
     pub virtual_liquidity: Option<f64>,
     pub settled: Option<bool>,
+    pub outcome: Option<String>,
+    /// SPL mint; None for native SOL markets
+    pub mint: Option<String>,
+    pub decimals: i16,
+    /// Protocol fee on winnings, in basis points
+    pub fee_bps: i16,
+    /// Fees collected by claims so far (base units)
+    pub fees_accrued: f64,
+    /// Range markets only: bucket bounds (bps change from open) and pools
+    pub bucket_bounds: Vec<i32>,
+    pub bucket_pools_weighted: Vec<f64>,
+    /// Volatility markets only: GREEN wins if high - low exceeds this
+    pub volatility_threshold_bps: Option<i32>,
+    /// Head-to-head markets only: GREEN wins if `asset` returns more than
+    /// this one over the candle
+    pub rival_asset: Option<String>,
+    pub rival_open_price: Option<f64>,
+    pub rival_close_price: Option<f64>,
     pub created_at: Option<DateTime<Utc>>,
 }
 
@@ -36,9 +64,56 @@ pub struct Bet {
     pub effective_stake: f64,
     pub payout: Option<f64>,
     pub claimed: Option<bool>,
+    pub mint: Option<String>,
+    pub decimals: i16,
     pub created_at: Option<DateTime<Utc>>,
 }
 
+/// Row of the `assets` table, mirroring an on-chain AssetConfig.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Asset {
+    pub symbol: String,
+    pub price_decimals: i16,
+    /// Pyth price account used for on-chain settlement
+    pub price_feed: String,
+    pub enabled: bool,
+}
+
+impl Asset {
+    /// Fixed-point price as stored in the asset's markets.
+    pub fn to_on_chain_price(&self, price: f64) -> u64 {
+        (price * 10f64.powi(self.price_decimals as i32)) as u64
+    }
+}
+
+/// What a claim on a settled market pays out.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PayoutKind {
+    None,
+    Win,
+    Refund,
+    /// Win or refund still inside the settlement dispute window
+    Pending,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserPayout {
+    pub kind: PayoutKind,
+    pub lamports: i64,
+}
+
+impl UserPayout {
+    pub fn none() -> Self {
+        UserPayout { kind: PayoutKind::None, lamports: 0 }
+    }
+
+    /// Something is owed and the program will pay it out now.
+    pub fn claimable(&self) -> bool {
+        self.lamports > 0 && self.kind != PayoutKind::Pending
+    }
+}
+
 //
 // Insert Market — returns DB ID
 //
@@ -50,17 +125,48 @@ pub async fn insert_market(
     end_time: DateTime<Utc>,
     lock_time: DateTime<Utc>,
     open_price: f64,
+    mint: Option<&str>,
+    decimals: i16,
+    virtual_liquidity: u64,
+    bucket_bounds: &[i32],
+    strike_price: Option<f64>,
+    volatility_threshold_bps: Option<u32>,
+    rival_asset: Option<&str>,
+    rival_open_price: Option<f64>,
 ) -> Result<i64> {
     let open_bd = BigDecimal::from_f64(open_price)
         .ok_or_else(|| anyhow::anyhow!("Failed to convert open_price"))?;
+    let strike_bd = match strike_price {
+        Some(p) => Some(
+            BigDecimal::from_f64(p).ok_or_else(|| anyhow::anyhow!("Failed to convert strike_price"))?,
+        ),
+        None => None,
+    };
+    let rival_open_bd = match rival_open_price {
+        Some(p) => Some(
+            BigDecimal::from_f64(p)
+                .ok_or_else(|| anyhow::anyhow!("Failed to convert rival_open_price"))?,
+        ),
+        None => None,
+    };
+    let vl_bd = BigDecimal::from_u64(virtual_liquidity).unwrap();
+
+    // Same starting pools as create_market: Green/Red, or one per bucket
+    let (binary_bd, bucket_pools) = if bucket_bounds.is_empty() {
+        (vl_bd.clone(), Vec::new())
+    } else {
+        (BigDecimal::from(0), vec![vl_bd.clone(); bucket_bounds.len() + 1])
+    };
 
     let row = sqlx::query!(
         r#"
         INSERT INTO markets (
             market_id, asset, start_time, end_time, lock_time,
-            open_price, green_pool_weighted, red_pool_weighted, virtual_liquidity
+            open_price, green_pool_weighted, red_pool_weighted, virtual_liquidity,
+            mint, decimals, bucket_bounds, bucket_pools_weighted, strike_price,
+            volatility_threshold_bps, rival_asset, rival_open_price
         )
-        VALUES ($1, $2, $3, $4, $5, $6, 100, 100, 100)
+        VALUES ($1, $2, $3, $4, $5, $6, $10, $10, $9, $7, $8, $11, $12, $13, $14, $15, $16)
         RETURNING id
         "#,
         market_id,
@@ -68,7 +174,17 @@ pub async fn insert_market(
         start_time,
         end_time,
         lock_time,
-        open_bd
+        open_bd,
+        mint,
+        decimals,
+        vl_bd,
+        binary_bd,
+        bucket_bounds,
+        &bucket_pools,
+        strike_bd,
+        volatility_threshold_bps.map(|t| t as i32),
+        rival_asset,
+        rival_open_bd
     )
     .fetch_one(pool)
     .await?;
@@ -77,7 +193,68 @@ pub async fn insert_market(
 }
 
 //
-// Insert Bet
+// Upsert an asset read from its on-chain AssetConfig
+//
+pub async fn upsert_asset(pool: &Pool<Postgres>, asset: &AssetConfig) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO assets (symbol, price_decimals, price_feed, enabled)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (symbol) DO UPDATE
+        SET price_decimals = EXCLUDED.price_decimals,
+            price_feed = EXCLUDED.price_feed,
+            enabled = EXCLUDED.enabled,
+            updated_at = NOW()
+        "#,
+        asset.symbol,
+        asset.price_decimals as i16,
+        asset.price_feed.to_string(),
+        asset.enabled
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+//
+// Enabled assets, i.e. the symbols new markets can be created for
+//
+pub async fn get_enabled_assets(pool: &Pool<Postgres>) -> Result<Vec<Asset>> {
+    let rows = sqlx::query_as!(
+        Asset,
+        r#"
+        SELECT symbol, price_decimals, price_feed, enabled
+        FROM assets
+        WHERE enabled = true
+        ORDER BY symbol ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get_asset(pool: &Pool<Postgres>, symbol: &str) -> Result<Asset> {
+    let asset = sqlx::query_as!(
+        Asset,
+        r#"
+        SELECT symbol, price_decimals, price_feed, enabled
+        FROM assets
+        WHERE symbol = $1
+        "#,
+        symbol
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(asset)
+}
+
+//
+// Insert Bet (top-ups accumulate into the wallet's existing position;
+// a withdrawn position is replaced, as the program closed its account)
 //
 pub async fn insert_bet(
     pool: &Pool<Postgres>,
@@ -87,6 +264,8 @@ pub async fn insert_bet(
     amount: f64,
     weight: f64,
     effective_stake: f64,
+    mint: Option<&str>,
+    decimals: i16,
 ) -> Result<()> {
     let amount_bd = BigDecimal::from_f64(amount).unwrap();
     let weight_bd = BigDecimal::from_f64(weight).unwrap();
@@ -95,16 +274,36 @@ pub async fn insert_bet(
     sqlx::query!(
         r#"
         INSERT INTO bets (
-            wallet, market_id, side, amount, weight, effective_stake
+            wallet, market_id, side, amount, weight, effective_stake,
+            mint, decimals
         )
-        VALUES ($1, $2, $3, $4, $5, $6)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (wallet, market_id)
+        DO UPDATE SET
+            side = EXCLUDED.side,
+            amount = CASE WHEN bets.withdrawn THEN EXCLUDED.amount
+                     ELSE bets.amount + EXCLUDED.amount END,
+            effective_stake = CASE WHEN bets.withdrawn THEN EXCLUDED.effective_stake
+                              ELSE bets.effective_stake + EXCLUDED.effective_stake END,
+            weight = CASE WHEN bets.withdrawn THEN EXCLUDED.weight
+                     ELSE TRUNC(
+                         (bets.effective_stake + EXCLUDED.effective_stake) * 100
+                         / (bets.amount + EXCLUDED.amount)
+                     ) END,
+            payout = CASE WHEN bets.withdrawn THEN 0 ELSE bets.payout END,
+            claimed = CASE WHEN bets.withdrawn THEN false ELSE bets.claimed END,
+            exit_penalty = CASE WHEN bets.withdrawn THEN 0 ELSE bets.exit_penalty END,
+            withdrawn_at = CASE WHEN bets.withdrawn THEN NULL ELSE bets.withdrawn_at END,
+            withdrawn = false
         "#,
         wallet,
         market_id,
         side,
         amount_bd,
         weight_bd,
-        stake_bd
+        stake_bd,
+        mint,
+        decimals
     )
     .execute(pool)
     .await?;
@@ -119,20 +318,35 @@ pub async fn update_market_settlement(
     pool: &Pool<Postgres>,
     market_id: i64,
     close_price: f64,
+    high_price: f64,
+    low_price: f64,
+    rival_close_price: Option<f64>,
     settled: bool,
+    outcome: &str,
 ) -> Result<()> {
     let close_bd = BigDecimal::from_f64(close_price).unwrap();
+    let high_bd = BigDecimal::from_f64(high_price).unwrap();
+    let low_bd = BigDecimal::from_f64(low_price).unwrap();
+    let rival_close_bd = rival_close_price.and_then(BigDecimal::from_f64);
 
     sqlx::query!(
         r#"
         UPDATE markets
         SET close_price = $2,
-            settled = $3
+            settled = $3,
+            outcome = $4,
+            high_price = $5,
+            low_price = $6,
+            rival_close_price = $7
         WHERE market_id = $1
         "#,
         market_id,
         close_bd,
-        settled
+        settled,
+        outcome,
+        high_bd,
+        low_bd,
+        rival_close_bd
     )
     .execute(pool)
     .await?;
@@ -140,6 +354,109 @@ pub async fn update_market_settlement(
     Ok(())
 }
 
+//
+// Mark Market Voided (admin cancel; every bet is refunded)
+//
+pub async fn mark_market_voided(pool: &Pool<Postgres>, market_id: i64) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE markets
+        SET settled = true,
+            outcome = 'VOID',
+            finalizes_at = NOW()
+        WHERE market_id = $1
+        "#,
+        market_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+//
+// Update Market Finality (end of the on-chain dispute window, unix secs)
+//
+pub async fn update_market_finalizes_at(
+    pool: &Pool<Postgres>,
+    market_id: i64,
+    finalizes_at: i64,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE markets
+        SET finalizes_at = to_timestamp($2)
+        WHERE market_id = $1
+        "#,
+        market_id,
+        finalizes_at as f64
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+//
+// Update Market Pools (copied from the on-chain MarketAccount)
+//
+pub async fn update_market_pools(
+    pool: &Pool<Postgres>,
+    market_id: i64,
+    green_pool_weighted: u64,
+    red_pool_weighted: u64,
+    bucket_pools_weighted: &[u64],
+) -> Result<()> {
+    let green_bd = BigDecimal::from_u64(green_pool_weighted).unwrap();
+    let red_bd = BigDecimal::from_u64(red_pool_weighted).unwrap();
+    let buckets_bd: Vec<BigDecimal> = bucket_pools_weighted
+        .iter()
+        .map(|&p| BigDecimal::from_u64(p).unwrap())
+        .collect();
+
+    sqlx::query!(
+        r#"
+        UPDATE markets
+        SET green_pool_weighted = $2,
+            red_pool_weighted = $3,
+            bucket_pools_weighted = $4
+        WHERE market_id = $1
+        "#,
+        market_id,
+        green_bd,
+        red_bd,
+        &buckets_bd
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+//
+// DB label for an on-chain market outcome
+//
+pub fn outcome_label(outcome: &MarketOutcome) -> String {
+    match outcome {
+        MarketOutcome::Pending => "PENDING".into(),
+        MarketOutcome::Green => "GREEN".into(),
+        MarketOutcome::Red => "RED".into(),
+        MarketOutcome::Void => "VOID".into(),
+        MarketOutcome::Bucket(i) => format!("BUCKET_{}", i),
+    }
+}
+
+//
+// Bet side for a DB label ("GREEN", "RED" or "BUCKET_<n>")
+//
+pub fn parse_side(label: &str) -> Option<BetSide> {
+    match label.to_uppercase().as_str() {
+        "GREEN" => Some(BetSide::Green),
+        "RED" => Some(BetSide::Red),
+        other => other.strip_prefix("BUCKET_")?.parse().ok().map(BetSide::Bucket),
+    }
+}
+
 //
 // Update PnL
 //
@@ -177,8 +494,11 @@ pub async fn get_latest_market(pool: &Pool<Postgres>) -> Result<Market> {
         r#"
         SELECT 
             id, market_id, asset, start_time, end_time, lock_time,
-            open_price, close_price, green_pool_weighted, red_pool_weighted,
-            virtual_liquidity, settled, created_at
+            open_price, strike_price, close_price, high_price, low_price,
+            green_pool_weighted, red_pool_weighted,
+            virtual_liquidity, settled, outcome, mint, decimals, fee_bps, fees_accrued,
+            bucket_bounds, bucket_pools_weighted, volatility_threshold_bps,
+            rival_asset, rival_open_price, rival_close_price, created_at
         FROM markets
         ORDER BY id DESC
         LIMIT 1
@@ -195,11 +515,29 @@ pub async fn get_latest_market(pool: &Pool<Postgres>) -> Result<Market> {
         end_time: row.end_time,
         lock_time: row.lock_time,
         open_price: row.open_price.and_then(|v| v.to_f64()),
+        strike_price: row.strike_price.and_then(|v| v.to_f64()),
         close_price: row.close_price.and_then(|v| v.to_f64()),
+        high_price: row.high_price.and_then(|v| v.to_f64()),
+        low_price: row.low_price.and_then(|v| v.to_f64()),
         green_pool_weighted: row.green_pool_weighted.and_then(|v| v.to_f64()),
         red_pool_weighted: row.red_pool_weighted.and_then(|v| v.to_f64()),
         virtual_liquidity: row.virtual_liquidity.and_then(|v| v.to_f64()),
         settled: row.settled,
+        outcome: row.outcome,
+        mint: row.mint,
+        decimals: row.decimals,
+        fee_bps: row.fee_bps,
+        fees_accrued: row.fees_accrued.to_f64().unwrap_or(0.0),
+        bucket_bounds: row.bucket_bounds,
+        bucket_pools_weighted: row
+            .bucket_pools_weighted
+            .iter()
+            .map(|v| v.to_f64().unwrap_or(0.0))
+            .collect(),
+        volatility_threshold_bps: row.volatility_threshold_bps,
+        rival_asset: row.rival_asset,
+        rival_open_price: row.rival_open_price.and_then(|v| v.to_f64()),
+        rival_close_price: row.rival_close_price.and_then(|v| v.to_f64()),
         created_at: row.created_at,
     })
 }
@@ -212,8 +550,11 @@ pub async fn get_market_from_db(pool: &Pool<Postgres>, id: i64) -> Result<Market
         r#"
         SELECT 
             id, market_id, asset, start_time, end_time, lock_time,
-            open_price, close_price, green_pool_weighted, red_pool_weighted,
-            virtual_liquidity, settled, created_at
+            open_price, strike_price, close_price, high_price, low_price,
+            green_pool_weighted, red_pool_weighted,
+            virtual_liquidity, settled, outcome, mint, decimals, fee_bps, fees_accrued,
+            bucket_bounds, bucket_pools_weighted, volatility_threshold_bps,
+            rival_asset, rival_open_price, rival_close_price, created_at
         FROM markets
         WHERE market_id = $1
         LIMIT 1
@@ -231,11 +572,29 @@ pub async fn get_market_from_db(pool: &Pool<Postgres>, id: i64) -> Result<Market
         end_time: row.end_time,
         lock_time: row.lock_time,
         open_price: row.open_price.and_then(|v| v.to_f64()),
+        strike_price: row.strike_price.and_then(|v| v.to_f64()),
         close_price: row.close_price.and_then(|v| v.to_f64()),
+        high_price: row.high_price.and_then(|v| v.to_f64()),
+        low_price: row.low_price.and_then(|v| v.to_f64()),
         green_pool_weighted: row.green_pool_weighted.and_then(|v| v.to_f64()),
         red_pool_weighted: row.red_pool_weighted.and_then(|v| v.to_f64()),
         virtual_liquidity: row.virtual_liquidity.and_then(|v| v.to_f64()),
         settled: row.settled,
+        outcome: row.outcome,
+        mint: row.mint,
+        decimals: row.decimals,
+        fee_bps: row.fee_bps,
+        fees_accrued: row.fees_accrued.to_f64().unwrap_or(0.0),
+        bucket_bounds: row.bucket_bounds,
+        bucket_pools_weighted: row
+            .bucket_pools_weighted
+            .iter()
+            .map(|v| v.to_f64().unwrap_or(0.0))
+            .collect(),
+        volatility_threshold_bps: row.volatility_threshold_bps,
+        rival_asset: row.rival_asset,
+        rival_open_price: row.rival_open_price.and_then(|v| v.to_f64()),
+        rival_close_price: row.rival_close_price.and_then(|v| v.to_f64()),
         created_at: row.created_at,
     })
 }
@@ -248,8 +607,11 @@ pub async fn get_expired_unsettled_markets(pool: &Pool<Postgres>) -> Result<Vec<
         r#"
         SELECT 
             id, market_id, asset, start_time, end_time, lock_time,
-            open_price, close_price, green_pool_weighted, red_pool_weighted,
-            virtual_liquidity, settled, created_at
+            open_price, strike_price, close_price, high_price, low_price,
+            green_pool_weighted, red_pool_weighted,
+            virtual_liquidity, settled, outcome, mint, decimals, fee_bps, fees_accrued,
+            bucket_bounds, bucket_pools_weighted, volatility_threshold_bps,
+            rival_asset, rival_open_price, rival_close_price, created_at
         FROM markets
         WHERE settled = false 
         AND end_time <= NOW()
@@ -266,15 +628,103 @@ pub async fn get_expired_unsettled_markets(pool: &Pool<Postgres>) -> Result<Vec<
         end_time: row.end_time,
         lock_time: row.lock_time,
         open_price: row.open_price.and_then(|v| v.to_f64()),
+        strike_price: row.strike_price.and_then(|v| v.to_f64()),
         close_price: row.close_price.and_then(|v| v.to_f64()),
+        high_price: row.high_price.and_then(|v| v.to_f64()),
+        low_price: row.low_price.and_then(|v| v.to_f64()),
         green_pool_weighted: row.green_pool_weighted.and_then(|v| v.to_f64()),
         red_pool_weighted: row.red_pool_weighted.and_then(|v| v.to_f64()),
         virtual_liquidity: row.virtual_liquidity.and_then(|v| v.to_f64()),
         settled: row.settled,
+        outcome: row.outcome,
+        mint: row.mint,
+        decimals: row.decimals,
+        fee_bps: row.fee_bps,
+        fees_accrued: row.fees_accrued.to_f64().unwrap_or(0.0),
+        bucket_bounds: row.bucket_bounds,
+        bucket_pools_weighted: row
+            .bucket_pools_weighted
+            .iter()
+            .map(|v| v.to_f64().unwrap_or(0.0))
+            .collect(),
+        volatility_threshold_bps: row.volatility_threshold_bps,
+        rival_asset: row.rival_asset,
+        rival_open_price: row.rival_open_price.and_then(|v| v.to_f64()),
+        rival_close_price: row.rival_close_price.and_then(|v| v.to_f64()),
         created_at: row.created_at,
     }).collect())
 }
 
+//
+// Closable Markets (settled, past the on-chain grace period, not yet closed)
+//
+pub async fn get_closable_markets(
+    pool: &Pool<Postgres>,
+    grace_secs: i64,
+) -> Result<Vec<i64>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT market_id
+        FROM markets
+        WHERE settled = true
+        AND closed = false
+        AND end_time <= NOW() - make_interval(secs => $1)
+        ORDER BY market_id ASC
+        "#,
+        grace_secs as f64
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.market_id).collect())
+}
+
+//
+// Update Market Fees (mirrors the on-chain fee_bps / fees_accrued)
+//
+pub async fn update_market_fees(
+    pool: &Pool<Postgres>,
+    market_id: i64,
+    fee_bps: u16,
+    fees_accrued: u64,
+) -> Result<()> {
+    let fees_bd = BigDecimal::from_u64(fees_accrued).unwrap();
+
+    sqlx::query!(
+        r#"
+        UPDATE markets
+        SET fee_bps = $2,
+            fees_accrued = $3
+        WHERE market_id = $1
+        "#,
+        market_id,
+        fee_bps as i16,
+        fees_bd
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+//
+// Mark Market Closed (MarketAccount rent reclaimed)
+//
+pub async fn mark_market_closed(pool: &Pool<Postgres>, market_id: i64) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE markets
+        SET closed = true
+        WHERE market_id = $1
+        "#,
+        market_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 //
 // Active Markets
 //
@@ -283,8 +733,11 @@ pub async fn get_active_markets(pool: &Pool<Postgres>) -> Result<Vec<Market>> {
         r#"
         SELECT 
             id, market_id, asset, start_time, end_time, lock_time,
-            open_price, close_price, green_pool_weighted, red_pool_weighted,
-            virtual_liquidity, settled, created_at
+            open_price, strike_price, close_price, high_price, low_price,
+            green_pool_weighted, red_pool_weighted,
+            virtual_liquidity, settled, outcome, mint, decimals, fee_bps, fees_accrued,
+            bucket_bounds, bucket_pools_weighted, volatility_threshold_bps,
+            rival_asset, rival_open_price, rival_close_price, created_at
         FROM markets
         WHERE settled = false
         ORDER BY market_id ASC
@@ -301,33 +754,65 @@ pub async fn get_active_markets(pool: &Pool<Postgres>) -> Result<Vec<Market>> {
         end_time: row.end_time,
         lock_time: row.lock_time,
         open_price: row.open_price.and_then(|v| v.to_f64()),
+        strike_price: row.strike_price.and_then(|v| v.to_f64()),
         close_price: row.close_price.and_then(|v| v.to_f64()),
+        high_price: row.high_price.and_then(|v| v.to_f64()),
+        low_price: row.low_price.and_then(|v| v.to_f64()),
         green_pool_weighted: row.green_pool_weighted.and_then(|v| v.to_f64()),
         red_pool_weighted: row.red_pool_weighted.and_then(|v| v.to_f64()),
         virtual_liquidity: row.virtual_liquidity.and_then(|v| v.to_f64()),
         settled: row.settled,
+        outcome: row.outcome,
+        mint: row.mint,
+        decimals: row.decimals,
+        fee_bps: row.fee_bps,
+        fees_accrued: row.fees_accrued.to_f64().unwrap_or(0.0),
+        bucket_bounds: row.bucket_bounds,
+        bucket_pools_weighted: row
+            .bucket_pools_weighted
+            .iter()
+            .map(|v| v.to_f64().unwrap_or(0.0))
+            .collect(),
+        volatility_threshold_bps: row.volatility_threshold_bps,
+        rival_asset: row.rival_asset,
+        rival_open_price: row.rival_open_price.and_then(|v| v.to_f64()),
+        rival_close_price: row.rival_close_price.and_then(|v| v.to_f64()),
         created_at: row.created_at,
     }).collect())
 }
 
 //
-// Compute User Payout (Anchor-compatible formula)
-// Returns lamports (i64)
+// Compute User Payout (mirrors on-chain claim_reward exactly)
+// Returns lamports, tagged as a win or a refund
 //
 pub async fn compute_user_payout(
     pool: &Pool<Postgres>,
     wallet: &str,
     market_id: i64,
-) -> Result<i64> {
+) -> Result<UserPayout> {
     // Use a runtime query + try_get to avoid sqlx! compile-time type inference issues.
     let row_opt = sqlx::query(
         r#"
         SELECT
+            m.asset,
             m.open_price,
+            COALESCE(m.strike_price, m.open_price) AS strike_price,
             m.close_price,
+            m.high_price,
+            m.low_price,
+            m.volatility_threshold_bps,
+            m.rival_asset,
+            m.rival_open_price,
+            m.rival_close_price,
             m.virtual_liquidity,
             m.green_pool_weighted,
             m.red_pool_weighted,
+            m.outcome,
+            m.fee_bps,
+            m.finalizes_at,
+            m.bucket_bounds,
+            m.bucket_pools_weighted,
+            b.amount,
             b.effective_stake,
             b.side,
             b.claimed
@@ -344,64 +829,250 @@ pub async fn compute_user_payout(
 
     let row = match row_opt {
         Some(r) => r,
-        None => return Ok(0),
+        None => return Ok(UserPayout::none()),
     };
 
     // Extract as Options (works regardless of sqlx inferred compile-time types)
+    let symbol: String = row.try_get("asset")?;
     let open_bd: Option<BigDecimal> = row.try_get("open_price").ok();
+    let strike_bd: Option<BigDecimal> = row.try_get("strike_price").ok();
+    let high_bd: Option<BigDecimal> = row.try_get("high_price").ok();
+    let low_bd: Option<BigDecimal> = row.try_get("low_price").ok();
+    let volatility_opt: Option<i32> = row.try_get("volatility_threshold_bps").ok().flatten();
+    let rival_opt: Option<String> = row.try_get("rival_asset").ok().flatten();
+    let rival_open_bd: Option<BigDecimal> = row.try_get("rival_open_price").ok();
+    let rival_close_bd: Option<BigDecimal> = row.try_get("rival_close_price").ok();
     let close_bd: Option<BigDecimal> = row.try_get("close_price").ok();
     let vl_bd: Option<BigDecimal> = row.try_get("virtual_liquidity").ok();
     let g_bd: Option<BigDecimal> = row.try_get("green_pool_weighted").ok();
     let r_bd: Option<BigDecimal> = row.try_get("red_pool_weighted").ok();
+    let outcome_opt: Option<String> = row.try_get("outcome").ok();
+    let fee_bps_opt: Option<i16> = row.try_get("fee_bps").ok();
+    let finalizes_at_opt: Option<DateTime<Utc>> = row.try_get("finalizes_at").ok();
+    let bounds: Vec<i32> = row.try_get("bucket_bounds").unwrap_or_default();
+    let bucket_bd: Vec<BigDecimal> = row.try_get("bucket_pools_weighted").unwrap_or_default();
+    let amount_bd_opt: Option<BigDecimal> = row.try_get("amount").ok();
     let eff_bd_opt: Option<BigDecimal> = row.try_get("effective_stake").ok();
     let side_opt: Option<String> = row.try_get("side").ok();
     let claimed_opt: Option<bool> = row.try_get("claimed").ok();
 
-    // If claimed or no bet/effective stake -> zero
+    // If claimed or no bet -> zero
     if claimed_opt.unwrap_or(false) {
-        return Ok(0);
+        return Ok(UserPayout::none());
     }
-    let eff_bd = match eff_bd_opt {
-        Some(v) => v,
-        None => return Ok(0),
+    let amount = match amount_bd_opt.and_then(|v| v.to_u64()) {
+        Some(v) if v > 0 => v,
+        _ => return Ok(UserPayout::none()),
     };
+    let effective = eff_bd_opt.and_then(|v| v.to_u64()).unwrap_or(0);
 
-    let effective = eff_bd.to_f64().unwrap_or(0.0);
-    if effective <= 0.0 {
-        return Ok(0);
-    }
+    let virtual_liq = vl_bd.and_then(|v| v.to_u64()).unwrap_or(100);
+    let gpool = g_bd.and_then(|v| v.to_u64()).unwrap_or(100);
+    let rpool = r_bd.and_then(|v| v.to_u64()).unwrap_or(100);
+
+    // Every pool in BetSide::index order: Green/Red, or one per bucket
+    let pools: Vec<u64> = if bounds.is_empty() {
+        vec![gpool, rpool]
+    } else {
+        bucket_bd.iter().map(|v| v.to_u64().unwrap_or(virtual_liq)).collect()
+    };
+
+    // Prefer the outcome recorded from chain; older rows fall back to prices.
+    let outcome = match outcome_opt.as_deref() {
+        Some("GREEN") => MarketOutcome::Green,
+        Some("RED") => MarketOutcome::Red,
+        Some("VOID") => MarketOutcome::Void,
+        Some(label) if label.starts_with("BUCKET_") => match parse_side(label) {
+            Some(BetSide::Bucket(i)) => MarketOutcome::Bucket(i),
+            _ => return Err(anyhow::anyhow!("Bad outcome {} for market {}", label, market_id)),
+        },
+        _ => {
+            let strike = strike_bd.and_then(|v| v.to_f64()).unwrap_or(0.0);
+            let close = close_bd.and_then(|v| v.to_f64()).unwrap_or(0.0);
 
-    let open = open_bd.and_then(|v| v.to_f64()).unwrap_or(0.0);
-    let close = close_bd.and_then(|v| v.to_f64()).unwrap_or(0.0);
+            // Not settled (or zero close) -> no payout
+            if close == 0.0 {
+                return Ok(UserPayout::none());
+            }
 
-    // Not settled (or zero close) -> no payout
-    if close == 0.0 {
-        return Ok(0);
+            // Returns and candle ranges are computed at the scale the
+            // program stored each asset's prices in
+            let price = |v: Option<BigDecimal>| v.and_then(|v| v.to_f64()).unwrap_or(0.0);
+            if let Some(rival_symbol) = rival_opt.as_deref() {
+                let asset = get_asset(pool, &symbol).await?;
+                let rival = get_asset(pool, rival_symbol).await?;
+                resolve_head_to_head_outcome(
+                    asset.to_on_chain_price(price(open_bd)),
+                    asset.to_on_chain_price(close),
+                    rival.to_on_chain_price(price(rival_open_bd)),
+                    rival.to_on_chain_price(price(rival_close_bd)),
+                    gpool,
+                    rpool,
+                    virtual_liq,
+                )
+            } else if let Some(threshold) = volatility_opt {
+                let asset = get_asset(pool, &symbol).await?;
+                resolve_volatility_outcome(
+                    asset.to_on_chain_price(price(open_bd)),
+                    asset.to_on_chain_price(price(high_bd)),
+                    asset.to_on_chain_price(price(low_bd)),
+                    threshold as u32,
+                    gpool,
+                    rpool,
+                    virtual_liq,
+                )
+            } else {
+                // Only compared with each other, so any common scale will do;
+                // these markets can predate the asset registry
+                let (strike, close) = ((strike * 100.0) as u64, (close * 100.0) as u64);
+                if bounds.is_empty() {
+                    resolve_outcome(strike, close, gpool, rpool, virtual_liq)
+                } else {
+                    resolve_range_outcome(strike, close, &bounds, &pools, virtual_liq)
+                }
+            }
+        }
+    };
+
+    let user_side = side_opt.as_deref().and_then(parse_side).unwrap_or(BetSide::Red);
+    let fee_bps = fee_bps_opt.unwrap_or(0).max(0) as u16;
+
+    let payout = settled_payout(&outcome, &user_side, &pools, virtual_liq, amount, effective, fee_bps)
+        .ok_or_else(|| anyhow::anyhow!("Payout overflow for market {}", market_id))?;
+
+    // Still disputable on-chain: report the expected amount, not claimable yet
+    let in_dispute = finalizes_at_opt.map(|t| t > Utc::now()).unwrap_or(false);
+    if in_dispute && payout.kind != PayoutKind::None {
+        return Ok(UserPayout { kind: PayoutKind::Pending, ..payout });
     }
 
-    let winning_is_green = close > open;
-    let user_side = side_opt.unwrap_or_else(|| "RED".to_string()).to_uppercase();
-    let user_is_green = user_side == "GREEN";
+    Ok(payout)
+}
+
+//
+// What a bet on `side` is owed once `outcome` is known. Same integer math
+// as the program's MarketAccount::claim: principal + pro-rata losing
+// share, minus the protocol fee on the winnings; void refunds the stake.
+// None only on overflow.
+//
+pub fn settled_payout(
+    outcome: &MarketOutcome,
+    side: &BetSide,
+    pools: &[u64],
+    virtual_liq: u64,
+    amount: u64,
+    effective: u64,
+    fee_bps: u16,
+) -> Option<UserPayout> {
+    let winning_side = match outcome {
+        MarketOutcome::Pending => return Some(UserPayout::none()),
+        MarketOutcome::Void => {
+            return Some(UserPayout { kind: PayoutKind::Refund, lamports: amount as i64 });
+        }
+        MarketOutcome::Green => BetSide::Green,
+        MarketOutcome::Red => BetSide::Red,
+        MarketOutcome::Bucket(i) => BetSide::Bucket(*i),
+    };
 
-    if user_is_green != winning_is_green {
-        return Ok(0);
+    if *side != winning_side {
+        return Some(UserPayout::none());
     }
 
-    let virtual_liq = vl_bd.and_then(|v| v.to_f64()).unwrap_or(100.0);
-    let gpool = g_bd.and_then(|v| v.to_f64()).unwrap_or(100.0);
-    let rpool = r_bd.and_then(|v| v.to_f64()).unwrap_or(100.0);
+    let (total_winning, total_losing) = split_pools(pools, winning_side.index(), virtual_liq);
+    let gross = parimutuel_payout(amount, effective, total_winning, total_losing)?;
+    let payout = gross - protocol_fee(gross - amount, fee_bps);
 
-    let (winning_pool, losing_pool) = if winning_is_green { (gpool, rpool) } else { (rpool, gpool) };
+    Some(UserPayout { kind: PayoutKind::Win, lamports: payout as i64 })
+}
 
-    let total_winning = (winning_pool - virtual_liq).max(0.0);
-    let total_losing = (losing_pool - virtual_liq).max(0.0);
+//
+// Settled, still-open markets where the wallet has an unclaimed bet.
+// Returns (market_id, mint); mint is None for native SOL markets.
+//
+pub async fn get_unclaimed_markets(
+    pool: &Pool<Postgres>,
+    wallet: &str,
+) -> Result<Vec<(i64, Option<String>)>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT m.market_id, m.mint
+        FROM bets b
+        JOIN markets m ON m.market_id = b.market_id
+        WHERE b.wallet = $1
+        AND COALESCE(b.claimed, false) = false
+        AND m.settled = true
+        AND m.closed = false
+        ORDER BY m.market_id ASC
+        "#,
+        wallet
+    )
+    .fetch_all(pool)
+    .await?;
 
-    if total_winning <= 0.0 || total_losing <= 0.0 {
-        return Ok(0);
-    }
+    Ok(rows.into_iter().map(|row| (row.market_id, row.mint)).collect())
+}
 
-    let payout = (effective * total_losing) / total_winning;
-    Ok(payout as i64)
+//
+// Raw per-side totals rebuilt from bets, to backfill pre-totals markets.
+// Every position not withdrawn counts as an open bet, and markets from
+// before per-market limits get the default ones
+//
+pub async fn get_market_totals(pool: &Pool<Postgres>, market_id: i64) -> Result<MarketTotals> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            COALESCE(SUM(amount) FILTER (WHERE side = 'GREEN'), 0) AS green_total,
+            COALESCE(SUM(amount) FILTER (WHERE side = 'RED'), 0) AS red_total,
+            COUNT(*) FILTER (WHERE side = 'GREEN') AS green_bettors,
+            COUNT(*) FILTER (WHERE side = 'RED') AS red_bettors,
+            COALESCE(SUM(payout) FILTER (WHERE claimed = true), 0) AS total_claimed
+        FROM bets
+        WHERE market_id = $1
+        AND withdrawn = false
+        "#,
+    )
+    .bind(market_id)
+    .fetch_one(pool)
+    .await?;
+
+    let to_u64 = |col: &str| -> Result<u64> {
+        let bd: BigDecimal = row.try_get(col)?;
+        bd.to_u64().ok_or_else(|| anyhow::anyhow!("{} out of range", col))
+    };
+    let green_bettors: i64 = row.try_get("green_bettors")?;
+    let red_bettors: i64 = row.try_get("red_bettors")?;
+    let defaults = MarketParams::default();
+
+    Ok(MarketTotals {
+        green_total: to_u64("green_total")?,
+        red_total: to_u64("red_total")?,
+        green_bettors: green_bettors as u32,
+        red_bettors: red_bettors as u32,
+        total_claimed: to_u64("total_claimed")?,
+        open_bets: (green_bettors + red_bettors) as u32,
+        min_bet: defaults.min_bet,
+        max_bet: defaults.max_bet,
+    })
+}
+
+//
+// Wallets holding a position in a market
+//
+pub async fn get_market_wallets(pool: &Pool<Postgres>, market_id: i64) -> Result<Vec<String>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT wallet
+        FROM bets
+        WHERE market_id = $1
+        AND withdrawn = false
+        ORDER BY wallet ASC
+        "#,
+        market_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.wallet).collect())
 }
 
 //
@@ -432,6 +1103,57 @@ pub async fn mark_bet_claimed(
     Ok(())
 }
 
+//
+// Mark bet withdrawn after wallet signs withdraw_bet tx
+// Returns (refund, penalty) in base units, same math as on-chain
+//
+pub async fn mark_bet_withdrawn(
+    pool: &Pool<Postgres>,
+    wallet: &str,
+    market_id: i64,
+    exit_penalty_bps: u16,
+) -> Result<(u64, u64)> {
+    let row = sqlx::query!(
+        r#"
+        SELECT amount
+        FROM bets
+        WHERE wallet = $1 AND market_id = $2 AND withdrawn = false
+        "#,
+        wallet,
+        market_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("No open bet for {} on market {}", wallet, market_id))?;
+
+    let amount = row
+        .amount
+        .to_u64()
+        .ok_or_else(|| anyhow::anyhow!("Bet amount out of range"))?;
+    let penalty = protocol_fee(amount, exit_penalty_bps);
+    let refund = amount - penalty;
+
+    sqlx::query!(
+        r#"
+        UPDATE bets
+        SET withdrawn = true,
+            withdrawn_at = NOW(),
+            claimed = true,
+            payout = $3,
+            exit_penalty = $4
+        WHERE wallet = $1 AND market_id = $2
+        "#,
+        wallet,
+        market_id,
+        BigDecimal::from_u64(refund).unwrap(),
+        BigDecimal::from_u64(penalty).unwrap()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok((refund, penalty))
+}
+
 //
 // Record payout transaction
 //
@@ -525,7 +1247,8 @@ pub async fn get_user_positions(
             b.effective_stake,
             b.payout,
             EXTRACT(EPOCH FROM b.created_at)::BIGINT as timestamp,
-            COALESCE(m.settled, false) as settled
+            COALESCE(m.settled, false) as settled,
+            b.withdrawn
         FROM bets b
         LEFT JOIN markets m ON b.market_id = m.market_id
         WHERE b.wallet = $1
@@ -540,6 +1263,14 @@ pub async fn get_user_positions(
     let mut settled_positions = Vec::new();
 
     for row in rows {
+        let status = if row.withdrawn {
+            "WITHDRAWN"
+        } else if row.settled.unwrap_or(false) {
+            "SETTLED"
+        } else {
+            "OPEN"
+        };
+
         let pos = Position {
             market_id: row.market_id.unwrap_or(0),
             side: row.side,
@@ -548,10 +1279,10 @@ pub async fn get_user_positions(
             effective_stake: row.effective_stake.to_f64().unwrap_or(0.0),
             payout: row.payout.and_then(|v| v.to_f64()),
             timestamp: row.timestamp.unwrap_or(0),
-            status: if row.settled.unwrap_or(false) { "SETTLED".into() } else { "OPEN".into() },
+            status: status.into(),
         };
 
-        if row.settled.unwrap_or(false) {
+        if row.withdrawn || row.settled.unwrap_or(false) {
             settled_positions.push(pos);
         } else {
             open_positions.push(pos);
@@ -576,7 +1307,7 @@ pub async fn get_enhanced_pnl(pool: &Pool<Postgres>, wallet: &str) -> Result<Enh
     let pnl_row = sqlx::query!(
         r#"
         SELECT 
-            COALESCE(SUM(b.payout - b.effective_stake), 0) as total_pnl
+            COALESCE(SUM(b.payout - b.amount), 0) as total_pnl
         FROM bets b
         INNER JOIN markets m ON b.market_id = m.market_id
         WHERE b.wallet = $1 AND m.settled = true AND b.payout IS NOT NULL
@@ -592,7 +1323,7 @@ pub async fn get_enhanced_pnl(pool: &Pool<Postgres>, wallet: &str) -> Result<Enh
         r#"
         SELECT 
             COUNT(*) as total,
-            SUM(CASE WHEN b.payout > b.effective_stake THEN 1 ELSE 0 END) as wins
+            SUM(CASE WHEN b.payout > b.amount THEN 1 ELSE 0 END) as wins
         FROM bets b
         INNER JOIN markets m ON b.market_id = m.market_id
         WHERE b.wallet = $1 AND m.settled = true AND b.payout IS NOT NULL
@@ -610,7 +1341,7 @@ pub async fn get_enhanced_pnl(pool: &Pool<Postgres>, wallet: &str) -> Result<Enh
         r#"
         SELECT 
             b.payout,
-            b.effective_stake,
+            b.amount,
             b.created_at
         FROM bets b
         INNER JOIN markets m ON b.market_id = m.market_id
@@ -626,7 +1357,7 @@ pub async fn get_enhanced_pnl(pool: &Pool<Postgres>, wallet: &str) -> Result<Enh
     let mut streak = 0;
     for row in streak_rows {
         let payout = row.payout.and_then(|v| v.to_f64()).unwrap_or(0.0);
-        let stake = row.effective_stake.to_f64().unwrap_or(0.0);
+        let stake = row.amount.to_f64().unwrap_or(0.0);
         if payout > stake {
             streak += 1;
         } else {
@@ -639,4 +1370,55 @@ pub async fn get_enhanced_pnl(pool: &Pool<Postgres>, wallet: &str) -> Result<Enh
         win_rate,
         streak,
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Same markets as the program's claim tests: the amounts must match
+    // what MarketAccount::claim pays out on-chain.
+
+    #[test]
+    fn winners_match_the_program_payout() {
+        // Weighted 1_000 and 2_100 on Green, 2_000 on Red, 100 virtual each
+        let pools = [100 + 3_100, 100 + 2_000];
+        let win = |amount, effective, fee_bps| {
+            settled_payout(&MarketOutcome::Green, &BetSide::Green, &pools, 100, amount, effective, fee_bps)
+                .unwrap()
+        };
+
+        assert_eq!(win(1_000, 1_000, 0).lamports, 1_645);
+        assert_eq!(win(3_000, 2_100, 0).lamports, 4_354);
+
+        // 1% of the winnings goes to the protocol
+        let payout = win(1_000, 1_000, 100);
+        assert_eq!((payout.kind, payout.lamports), (PayoutKind::Win, 1_639));
+        assert_eq!(win(3_000, 2_100, 100).lamports, 4_341);
+
+        let lost = settled_payout(&MarketOutcome::Green, &BetSide::Red, &pools, 100, 2_000, 2_000, 100)
+            .unwrap();
+        assert_eq!((lost.kind, lost.lamports), (PayoutKind::None, 0));
+    }
+
+    #[test]
+    fn void_refunds_the_stake_without_a_fee() {
+        let pools = [100 + 1_000, 100 + 2_500];
+        let refund = settled_payout(&MarketOutcome::Void, &BetSide::Red, &pools, 100, 2_500, 1_750, 100)
+            .unwrap();
+
+        assert_eq!((refund.kind, refund.lamports), (PayoutKind::Refund, 2_500));
+    }
+
+    #[test]
+    fn range_winners_match_the_program_payout() {
+        let pools = [100 + 500, 100, 100 + 1_000, 100 + 1_500];
+        let outcome = MarketOutcome::Bucket(2);
+
+        let win = settled_payout(&outcome, &BetSide::Bucket(2), &pools, 100, 500, 500, 0).unwrap();
+        assert_eq!(win.lamports, 1_500);
+
+        let lost = settled_payout(&outcome, &BetSide::Bucket(3), &pools, 100, 500, 500, 0).unwrap();
+        assert_eq!(lost.lamports, 0);
+    }
+}
@@ -0,0 +1,899 @@
+use axum::{
+    Router,
+    routing::post,
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::state::AppState;
+use crate::repository::{
+    get_asset, get_market_from_db, get_market_totals, get_market_wallets, mark_market_voided,
+    outcome_label, update_market_settlement, upsert_asset,
+};
+use candle_markets::state::PRICE_DECIMALS;
+use crate::solana_client::SolanaClient;
+
+/// Header carrying the shared admin secret (ADMIN_API_KEY).
+const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+/// ---------------------------------------------------------------------------
+/// ADMIN ROUTES (require x-admin-key)
+/// ---------------------------------------------------------------------------
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/market/:id/void", post(void_market_handler))
+        .route("/market/:id/resettle", post(resettle_market_handler))
+        .route("/market/:id/migrate", post(migrate_market_handler))
+        .route("/migrate", post(migrate_globals_handler))
+        .route("/dispute", post(set_dispute_handler))
+        .route("/asset", post(register_asset_handler))
+        .route("/asset/update", post(update_asset_handler))
+        .route("/settlers", post(set_settlers_handler))
+        .route("/pause", post(pause_handler))
+        .route("/market/:id/pause", post(pause_market_handler))
+        .route("/fee", post(set_fee_handler))
+        .route("/exit-penalty", post(set_exit_penalty_handler))
+        .route("/fee-vault", post(init_fee_vault_handler))
+        .route("/fees/withdraw", post(withdraw_fees_handler))
+        .route("/treasury/withdraw/propose", post(propose_withdrawal_handler))
+        .route("/treasury/withdraw/execute", post(execute_withdrawal_handler))
+        .route("/treasury/legacy-escrow/release", post(release_legacy_escrow_handler))
+}
+
+/// Rejects the request unless it carries the configured admin key.
+fn authorize(headers: &HeaderMap, state: &AppState) -> Result<(), Json<serde_json::Value>> {
+    let expected = match state.admin_api_key.as_deref() {
+        Some(k) => k,
+        None => {
+            return Err(Json(json!({
+                "ok": false,
+                "error": "admin routes are disabled (ADMIN_API_KEY not set)"
+            })))
+        }
+    };
+
+    let provided = headers
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+
+    if provided != Some(expected) {
+        return Err(Json(json!({ "ok": false, "error": "unauthorized" })));
+    }
+
+    Ok(())
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/market/:id/void
+/// ---------------------------------------------------------------------------
+/// Cancels a market (e.g. created with a bad open price). Works any time
+/// until settlement is final; every bettor can then reclaim their stake
+/// via claim_reward.
+async fn void_market_handler(
+    Path(market_id): Path<i64>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let sol = state.sol.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        sol.void_market_and_send(market_id as u64)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::info!("[ADMIN] Voided market_id={} tx={}", market_id, sig);
+
+            if let Err(e) = mark_market_voided(&state.pool, market_id).await {
+                tracing::error!("[ADMIN] DB void update failed: market_id={} err={:?}", market_id, e);
+            }
+
+            Json(json!({ "ok": true, "market_id": market_id, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/market/:id/resettle
+/// ---------------------------------------------------------------------------
+/// Body: { "close_price": 64123.45, "high_price": 65010.0, "low_price": 63200.5,
+///         "rival_close_price": 3120.5 }
+///
+/// Corrects a wrong close price while the market's dispute window is open.
+/// High and low default to the close; volatility markets need the real ones.
+/// Head-to-head markets also need the rival asset's close.
+#[derive(Debug, Deserialize)]
+pub struct ResettleBody {
+    pub close_price: f64,
+    pub high_price: Option<f64>,
+    pub low_price: Option<f64>,
+    pub rival_close_price: Option<f64>,
+}
+
+async fn resettle_market_handler(
+    Path(market_id): Path<i64>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<ResettleBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    // Prices are stored on-chain with the asset's price decimals
+    let market = match get_market_from_db(&state.pool, market_id).await {
+        Ok(market) => market,
+        Err(e) => return Json(json!({ "ok": false, "error": e.to_string() })),
+    };
+    let asset = match get_asset(&state.pool, &market.asset).await {
+        Ok(asset) => asset,
+        Err(e) => return Json(json!({ "ok": false, "error": e.to_string() })),
+    };
+    let high_price = body.high_price.unwrap_or(body.close_price);
+    let low_price = body.low_price.unwrap_or(body.close_price);
+    let on_chain_close = asset.to_on_chain_price(body.close_price);
+    let on_chain_high = asset.to_on_chain_price(high_price);
+    let on_chain_low = asset.to_on_chain_price(low_price);
+
+    // ...and the rival's close with the rival's
+    let on_chain_rival_close = match (&market.rival_asset, body.rival_close_price) {
+        (Some(rival), Some(price)) => match get_asset(&state.pool, rival).await {
+            Ok(rival) => rival.to_on_chain_price(price),
+            Err(e) => return Json(json!({ "ok": false, "error": e.to_string() })),
+        },
+        (Some(_), None) => {
+            return Json(json!({ "ok": false, "error": "rival_close_price is required" }));
+        }
+        (None, _) => 0,
+    };
+
+    let sol = state.sol.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let sig = sol.resettle_market_and_send(
+            market_id as u64,
+            on_chain_close,
+            on_chain_high,
+            on_chain_low,
+            on_chain_rival_close,
+        )?;
+        let market = sol.fetch_market(market_id as u64)?;
+        Ok::<_, anyhow::Error>((sig, market))
+    })
+    .await;
+
+    match result {
+        Ok(Ok((sig, market))) => {
+            let outcome = outcome_label(&market.outcome);
+            tracing::warn!("[ADMIN] Re-settled market_id={} outcome={} tx={}", market_id, outcome, sig);
+
+            if let Err(e) = update_market_settlement(
+                &state.pool,
+                market_id,
+                body.close_price,
+                high_price,
+                low_price,
+                body.rival_close_price,
+                true,
+                &outcome,
+            )
+            .await
+            {
+                tracing::error!("[ADMIN] DB resettle update failed: market_id={} err={:?}", market_id, e);
+            }
+
+            Json(json!({ "ok": true, "market_id": market_id, "outcome": outcome, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/market/:id/migrate
+/// ---------------------------------------------------------------------------
+/// Upgrades a market created before account versioning to the current
+/// layout, backfilling its raw totals, bettor counts and open bets from
+/// the bets table, then upgrades its vault and every bet on it. Accounts
+/// already on the current layout are reported as failed and left alone. A
+/// market from before per-market vaults has none yet: one is created and
+/// the stakes the market still owes are moved into it from the treasury,
+/// which has to be migrated first (POST /admin/migrate).
+async fn migrate_market_handler(
+    Path(market_id): Path<i64>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let totals = match get_market_totals(&state.pool, market_id).await {
+        Ok(t) => t,
+        Err(e) => return Json(json!({ "ok": false, "error": e.to_string() })),
+    };
+    let wallets = match get_market_wallets(&state.pool, market_id).await {
+        Ok(w) => w,
+        Err(e) => return Json(json!({ "ok": false, "error": e.to_string() })),
+    };
+
+    let sol = state.sol.clone();
+    let backfill = totals.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let market_result = sol.migrate_market_and_send(market_id as u64, &backfill);
+
+        let (market_pda, _) = sol.derive_market_pda(market_id as u64);
+        let (vault_pda, _) = sol.derive_market_vault_pda(&market_pda);
+
+        let mut accounts = Vec::new();
+        let escrow_result = match sol.account_exists(&vault_pda) {
+            Ok(true) => {
+                accounts.push(vault_pda);
+                None
+            }
+            Ok(false) => Some(migrate_escrow(&sol, market_id as u64)),
+            Err(e) => Some(Err(e)),
+        };
+        for wallet in &wallets {
+            if let Ok(user) = Pubkey::from_str(wallet) {
+                accounts.push(sol.derive_bet_pda(&user, &market_pda).0);
+            }
+        }
+
+        (market_result, escrow_result, migrate_accounts(&sol, &accounts))
+    })
+    .await;
+
+    match result {
+        Ok((_, Some(Err(e)), accounts)) => {
+            Json(json!({ "ok": false, "error": e.to_string(), "accounts": accounts }))
+        }
+        Ok((Ok(sig), escrow, accounts)) => {
+            tracing::warn!("[ADMIN] Migrated market_id={} tx={}", market_id, sig);
+            Json(json!({
+                "ok": true,
+                "market_id": market_id,
+                "green_total": totals.green_total,
+                "red_total": totals.red_total,
+                "green_bettors": totals.green_bettors,
+                "red_bettors": totals.red_bettors,
+                "total_claimed": totals.total_claimed,
+                "open_bets": totals.open_bets,
+                "tx": sig,
+                "escrow_txs": escrow.and_then(Result::ok),
+                "accounts": accounts
+            }))
+        }
+        Ok((Err(e), _, accounts)) => {
+            Json(json!({ "ok": false, "error": e.to_string(), "accounts": accounts }))
+        }
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/migrate
+/// ---------------------------------------------------------------------------
+/// Upgrades the program-wide accounts (treasury, config, SOL fee vault,
+/// settler set) created before account versioning.
+async fn migrate_globals_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let sol = state.sol.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let accounts = [
+            sol.derive_treasury_pda().0,
+            sol.derive_config_pda().0,
+            sol.derive_fee_vault_pda(None).0,
+            sol.derive_settler_set_pda().0,
+        ];
+        migrate_accounts(&sol, &accounts)
+    })
+    .await;
+
+    match result {
+        Ok(accounts) => Json(json!({ "ok": true, "accounts": accounts })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// Gives a market from before per-market vaults its vault, then moves its
+/// tokens too if it is an SPL market. Returns the signatures sent.
+fn migrate_escrow(sol: &SolanaClient, market_id: u64) -> anyhow::Result<Vec<String>> {
+    let mut sigs = vec![sol.migrate_market_escrow_and_send(market_id)?];
+
+    let market = sol.fetch_market(market_id)?;
+    if !market.is_native() {
+        sigs.push(sol.migrate_market_token_escrow_and_send(market_id, &market.mint)?);
+    }
+
+    Ok(sigs)
+}
+
+/// Sends migrate_account for each address, one transaction each, and
+/// reports the outcome per account.
+fn migrate_accounts(sol: &SolanaClient, accounts: &[Pubkey]) -> Vec<serde_json::Value> {
+    accounts
+        .iter()
+        .map(|account| match sol.migrate_account_and_send(*account) {
+            Ok(sig) => {
+                tracing::info!("[ADMIN] Migrated account {} tx={}", account, sig);
+                json!({ "account": account.to_string(), "ok": true, "tx": sig })
+            }
+            Err(e) => json!({ "account": account.to_string(), "ok": false, "error": e.to_string() }),
+        })
+        .collect()
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/dispute
+/// ---------------------------------------------------------------------------
+/// Body: { "dispute_period": 3600, "resolver": "pubkey" }
+///
+/// Seconds claims stay locked after settlement, and the extra key (besides
+/// the admin) allowed to re-settle or void during that window.
+#[derive(Debug, Deserialize)]
+pub struct DisputeBody {
+    pub dispute_period: i64,
+    pub resolver: String,
+}
+
+async fn set_dispute_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<DisputeBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let resolver = match Pubkey::from_str(&body.resolver) {
+        Ok(r) => r,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid resolver: {}", e) })),
+    };
+
+    let sol = state.sol.clone();
+    let dispute_period = body.dispute_period;
+    let result = tokio::task::spawn_blocking(move || {
+        sol.set_dispute_config_and_send(dispute_period, resolver)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => Json(json!({ "ok": true, "dispute_period": dispute_period, "tx": sig })),
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/asset
+/// ---------------------------------------------------------------------------
+/// Body: { "symbol": "ETH/USDT", "price_feed": "pubkey", "price_decimals": 2 }
+///
+/// Registers a symbol markets can be created for. `price_decimals` defaults
+/// to cents and cannot be changed afterwards.
+#[derive(Debug, Deserialize)]
+pub struct RegisterAssetBody {
+    pub symbol: String,
+    pub price_feed: String,
+    pub price_decimals: Option<u8>,
+}
+
+async fn register_asset_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<RegisterAssetBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let price_feed = match Pubkey::from_str(&body.price_feed) {
+        Ok(p) => p,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid price_feed: {}", e) })),
+    };
+    let price_decimals = body.price_decimals.unwrap_or(PRICE_DECIMALS);
+
+    let sol = state.sol.clone();
+    let symbol = body.symbol.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let sig = sol.register_asset_and_send(&symbol, price_decimals, price_feed)?;
+        let asset = sol.fetch_asset_config(&symbol)?;
+        Ok::<_, anyhow::Error>((sig, asset))
+    })
+    .await;
+
+    match result {
+        Ok(Ok((sig, asset))) => {
+            tracing::warn!("[ADMIN] Registered asset {} tx={}", body.symbol, sig);
+
+            if let Err(e) = upsert_asset(&state.pool, &asset).await {
+                tracing::error!("[ADMIN] DB asset upsert failed: {} err={:?}", body.symbol, e);
+            }
+
+            Json(json!({ "ok": true, "symbol": body.symbol, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/asset/update
+/// ---------------------------------------------------------------------------
+/// Body: { "symbol": "ETH/USDT", "price_feed": "pubkey", "enabled": false }
+///
+/// Disabling an asset stops new markets; open ones run to settlement.
+#[derive(Debug, Deserialize)]
+pub struct UpdateAssetBody {
+    pub symbol: String,
+    pub price_feed: String,
+    pub enabled: bool,
+}
+
+async fn update_asset_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<UpdateAssetBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let price_feed = match Pubkey::from_str(&body.price_feed) {
+        Ok(p) => p,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid price_feed: {}", e) })),
+    };
+
+    let sol = state.sol.clone();
+    let symbol = body.symbol.clone();
+    let enabled = body.enabled;
+    let result = tokio::task::spawn_blocking(move || {
+        let sig = sol.update_asset_and_send(&symbol, price_feed, enabled)?;
+        let asset = sol.fetch_asset_config(&symbol)?;
+        Ok::<_, anyhow::Error>((sig, asset))
+    })
+    .await;
+
+    match result {
+        Ok(Ok((sig, asset))) => {
+            tracing::warn!(
+                "[ADMIN] Updated asset {} enabled={} tx={}",
+                body.symbol,
+                enabled,
+                sig
+            );
+
+            if let Err(e) = upsert_asset(&state.pool, &asset).await {
+                tracing::error!("[ADMIN] DB asset upsert failed: {} err={:?}", body.symbol, e);
+            }
+
+            Json(json!({ "ok": true, "symbol": body.symbol, "enabled": enabled, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/settlers
+/// ---------------------------------------------------------------------------
+/// Body: { "settlers": ["pubkey", ...], "threshold": 2, "tolerance_bps": 10 }
+///
+/// Switches settlement to M-of-N close price votes. Every settler node runs
+/// with SETTLEMENT_MODE=vote; single-key settle_market stops working.
+#[derive(Debug, Deserialize)]
+pub struct SettlersBody {
+    pub settlers: Vec<String>,
+    pub threshold: u8,
+    pub tolerance_bps: u16,
+}
+
+async fn set_settlers_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<SettlersBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let settlers: Result<Vec<Pubkey>, _> =
+        body.settlers.iter().map(|s| Pubkey::from_str(s)).collect();
+    let settlers = match settlers {
+        Ok(s) => s,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid settler: {}", e) })),
+    };
+
+    let sol = state.sol.clone();
+    let (threshold, tolerance_bps) = (body.threshold, body.tolerance_bps);
+    let result = tokio::task::spawn_blocking(move || {
+        sol.set_settlers_and_send(&settlers, threshold, tolerance_bps)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::warn!(
+                "[ADMIN] Settler set updated: {} keys, threshold {} tx={}",
+                body.settlers.len(),
+                threshold,
+                sig
+            );
+            Json(json!({ "ok": true, "threshold": threshold, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/pause
+/// POST /admin/market/:id/pause
+/// ---------------------------------------------------------------------------
+/// Body: { "paused": true | false }
+///
+/// Emergency stop. While paused, bets, settlement and claims fail on-chain,
+/// either for every market or only the given one.
+#[derive(Debug, Deserialize)]
+pub struct PauseBody {
+    pub paused: bool,
+}
+
+async fn pause_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<PauseBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let sol = state.sol.clone();
+    let paused = body.paused;
+    let result = tokio::task::spawn_blocking(move || {
+        sol.set_paused_and_send(paused)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::warn!("[ADMIN] Global pause set to {} tx={}", paused, sig);
+            Json(json!({ "ok": true, "paused": paused, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+async fn pause_market_handler(
+    Path(market_id): Path<i64>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<PauseBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let sol = state.sol.clone();
+    let paused = body.paused;
+    let result = tokio::task::spawn_blocking(move || {
+        sol.set_market_paused_and_send(market_id as u64, paused)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::warn!("[ADMIN] market_id={} paused={} tx={}", market_id, paused, sig);
+            Json(json!({ "ok": true, "market_id": market_id, "paused": paused, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// Parses an optional mint; None (native SOL) when absent.
+fn parse_mint(mint: Option<&str>) -> Result<Option<Pubkey>, Json<serde_json::Value>> {
+    match mint {
+        None => Ok(None),
+        Some(m) => Pubkey::from_str(m)
+            .map(Some)
+            .map_err(|e| Json(json!({ "ok": false, "error": format!("invalid mint: {}", e) }))),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/fee
+/// ---------------------------------------------------------------------------
+/// Body: { "fee_bps": 200 }
+///
+/// Protocol fee on winnings for markets created after this call.
+#[derive(Debug, Deserialize)]
+pub struct FeeBody {
+    pub fee_bps: u16,
+}
+
+async fn set_fee_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<FeeBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let sol = state.sol.clone();
+    let fee_bps = body.fee_bps;
+    let result = tokio::task::spawn_blocking(move || {
+        sol.set_fee_bps_and_send(fee_bps)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => Json(json!({ "ok": true, "fee_bps": fee_bps, "tx": sig })),
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/exit-penalty
+/// ---------------------------------------------------------------------------
+/// Body: { "exit_penalty_bps": 300 }
+///
+/// Share of the stake kept when a bettor withdraws before lock, for
+/// markets created after this call.
+#[derive(Debug, Deserialize)]
+pub struct ExitPenaltyBody {
+    pub exit_penalty_bps: u16,
+}
+
+async fn set_exit_penalty_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<ExitPenaltyBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let sol = state.sol.clone();
+    let exit_penalty_bps = body.exit_penalty_bps;
+    let result = tokio::task::spawn_blocking(move || {
+        sol.set_exit_penalty_and_send(exit_penalty_bps)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => Json(json!({ "ok": true, "exit_penalty_bps": exit_penalty_bps, "tx": sig })),
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/fee-vault
+/// ---------------------------------------------------------------------------
+/// Body: { "mint": "mint_pubkey" }   (omit mint for the SOL fee vault)
+///
+/// Must exist before claims on markets in that currency can go through.
+#[derive(Debug, Deserialize)]
+pub struct FeeVaultBody {
+    pub mint: Option<String>,
+}
+
+async fn init_fee_vault_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<FeeVaultBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let mint = match parse_mint(body.mint.as_deref()) {
+        Ok(m) => m,
+        Err(resp) => return resp,
+    };
+
+    let sol = state.sol.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        sol.initialize_fee_vault_and_send(mint)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => Json(json!({ "ok": true, "mint": body.mint, "tx": sig })),
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/fees/withdraw
+/// ---------------------------------------------------------------------------
+/// Body: { "amount": 1000000, "recipient": "pubkey", "mint": "mint_pubkey" }
+///
+/// Amount is in base units. For SPL mints the recipient must be a token
+/// account of that mint; omit mint to withdraw SOL fees.
+#[derive(Debug, Deserialize)]
+pub struct WithdrawFeesBody {
+    pub amount: u64,
+    pub recipient: String,
+    pub mint: Option<String>,
+}
+
+async fn withdraw_fees_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<WithdrawFeesBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let recipient = match Pubkey::from_str(&body.recipient) {
+        Ok(r) => r,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid recipient: {}", e) })),
+    };
+    let mint = match parse_mint(body.mint.as_deref()) {
+        Ok(m) => m,
+        Err(resp) => return resp,
+    };
+
+    let sol = state.sol.clone();
+    let amount = body.amount;
+    let result = tokio::task::spawn_blocking(move || {
+        sol.withdraw_fees_and_send(amount, recipient, mint)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::info!("[ADMIN] Withdrew {} fees to {} tx={}", amount, body.recipient, sig);
+            Json(json!({ "ok": true, "amount": amount, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/treasury/withdraw/propose
+/// POST /admin/treasury/withdraw/execute
+/// ---------------------------------------------------------------------------
+/// Body: { "lamports": 1000000000, "recipient": "pubkey" }
+///       (execute only needs "recipient", which must match the proposal)
+///
+/// Withdrawals are timelocked on-chain: execute only succeeds once the
+/// program's WITHDRAWAL_DELAY has passed since the proposal. Proposing
+/// again replaces the pending withdrawal; 0 lamports cancels it.
+#[derive(Debug, Deserialize)]
+pub struct ProposeWithdrawalBody {
+    pub lamports: u64,
+    pub recipient: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecuteWithdrawalBody {
+    pub recipient: String,
+}
+
+async fn propose_withdrawal_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<ProposeWithdrawalBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let recipient = match Pubkey::from_str(&body.recipient) {
+        Ok(r) => r,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid recipient: {}", e) })),
+    };
+
+    let sol = state.sol.clone();
+    let lamports = body.lamports;
+    let result = tokio::task::spawn_blocking(move || {
+        sol.propose_withdrawal_and_send(lamports, recipient)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::warn!("[ADMIN] Proposed treasury withdrawal of {} to {} tx={}", lamports, body.recipient, sig);
+            Json(json!({ "ok": true, "lamports": lamports, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+async fn execute_withdrawal_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<ExecuteWithdrawalBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let recipient = match Pubkey::from_str(&body.recipient) {
+        Ok(r) => r,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid recipient: {}", e) })),
+    };
+
+    let sol = state.sol.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        sol.execute_withdrawal_and_send(recipient)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::warn!("[ADMIN] Executed treasury withdrawal to {} tx={}", body.recipient, sig);
+            Json(json!({ "ok": true, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
+
+/// ---------------------------------------------------------------------------
+/// POST /admin/treasury/legacy-escrow/release
+/// ---------------------------------------------------------------------------
+/// Body: { "market_ids": [1700000000, ...] }
+///
+/// Migrating a version 0 treasury reserves its whole balance for the
+/// markets from before per-market vaults. This lowers the reserve to what
+/// the listed markets still owe, so list every such market whose escrow
+/// has not been migrated yet (POST /admin/market/:id/migrate).
+#[derive(Debug, Deserialize)]
+pub struct ReleaseLegacyEscrowBody {
+    pub market_ids: Vec<u64>,
+}
+
+async fn release_legacy_escrow_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<ReleaseLegacyEscrowBody>,
+) -> Json<serde_json::Value> {
+    if let Err(resp) = authorize(&headers, &state) {
+        return resp;
+    }
+
+    let sol = state.sol.clone();
+    let market_ids = body.market_ids.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        sol.release_legacy_escrow_and_send(&market_ids)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(sig)) => {
+            tracing::warn!("[ADMIN] Released legacy escrow, {} markets still owed tx={}", body.market_ids.len(), sig);
+            Json(json!({ "ok": true, "tx": sig }))
+        }
+        Ok(Err(e)) => Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    }
+}
@@ -4,17 +4,34 @@ use axum::{
 };
 use serde::Deserialize;
 use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::state::AppState;
-use crate::repository::{compute_user_payout, mark_bet_claimed, record_payout};
+use crate::repository::{
+    compute_user_payout,
+    get_unclaimed_markets,
+    mark_bet_claimed,
+    mark_bet_withdrawn,
+    record_payout,
+    update_market_fees,
+    update_market_pools,
+    UserPayout,
+};
 
 //
 // ----------------------------------------------------------
 //  GET /claimable/:market_id/:wallet
 // ----------------------------------------------------------
 //  Returns:
-//  { ok: true, claimable: bool, payout: lamports }
+//  { ok: true, claimable: bool, payout: lamports,
+//    kind: "WIN" | "REFUND" | "PENDING" | "NONE" }
+//
+//  kind = REFUND for void markets (flat candle / one-sided),
+//  where the bettor only gets their stake back.
+//  kind = PENDING while the settlement dispute window is open;
+//  payout is the expected amount but cannot be claimed yet.
 // ----------------------------------------------------------
 //
 pub async fn get_claimable_handler(
@@ -23,11 +40,12 @@ pub async fn get_claimable_handler(
 ) -> Json<serde_json::Value> {
     match compute_user_payout(&state.pool, &wallet, market_id).await {
         Ok(payout) => {
-            let claimable = payout > 0;
+            let claimable = payout.claimable();
             Json(json!({
                 "ok": true,
                 "claimable": claimable,
-                "payout": payout
+                "payout": payout.lamports,
+                "kind": payout.kind
             }))
         }
         Err(e) => Json(json!({
@@ -37,6 +55,79 @@ pub async fn get_claimable_handler(
     }
 }
 
+//
+// ----------------------------------------------------------
+//  GET /claimable/:wallet
+// ----------------------------------------------------------
+//  Every market the wallet can still claim, with the accounts
+//  claim_many expects as [market, user_bet, vault] triples.
+//
+//  Returns:
+//  { ok: true, total: lamports,
+//    markets: [{ market_id, payout, kind, mint,
+//                market, user_bet, vault, batchable }] }
+//
+//  batchable = false for SPL markets, which must go through
+//  claim_reward_token one market at a time. `total` only sums
+//  batchable (SOL) markets.
+// ----------------------------------------------------------
+//
+pub async fn get_all_claimable_handler(
+    Path(wallet): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let user = match Pubkey::from_str(&wallet) {
+        Ok(u) => u,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid wallet: {}", e) })),
+    };
+
+    let unclaimed = match get_unclaimed_markets(&state.pool, &wallet).await {
+        Ok(m) => m,
+        Err(e) => return Json(json!({ "ok": false, "error": e.to_string() })),
+    };
+
+    let mut markets = Vec::new();
+    let mut total: i64 = 0;
+
+    for (market_id, mint) in unclaimed {
+        let payout = match compute_user_payout(&state.pool, &wallet, market_id).await {
+            Ok(p) if p.claimable() => p,
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::warn!("[CLAIM] payout failed market_id={} wallet={} err={:?}", market_id, wallet, e);
+                continue;
+            }
+        };
+
+        let (market_pda, _) = state.sol.derive_market_pda(market_id as u64);
+        let (bet_pda, _) = state.sol.derive_bet_pda(&user, &market_pda);
+        let (vault_pda, _) = state.sol.derive_market_vault_pda(&market_pda);
+        let batchable = mint.is_none();
+
+        if batchable {
+            total += payout.lamports;
+        }
+
+        markets.push(json!({
+            "market_id": market_id,
+            "payout": payout.lamports,
+            "kind": payout.kind,
+            "mint": mint,
+            "market": market_pda.to_string(),
+            "user_bet": bet_pda.to_string(),
+            "vault": vault_pda.to_string(),
+            "batchable": batchable
+        }));
+    }
+
+    Json(json!({
+        "ok": true,
+        "wallet": wallet,
+        "total": total,
+        "markets": markets
+    }))
+}
+
 //
 // ----------------------------------------------------------
 //  POST /claim/record
@@ -49,7 +140,8 @@ pub async fn get_claimable_handler(
 //  }
 //
 //  After user signs and submits on-chain claim transaction,
-//  backend records payout + marks bet claimed.
+//  backend records payout + marks bet claimed, and refreshes
+//  the market's accrued fees from chain.
 // ----------------------------------------------------------
 //
 #[derive(Debug, Deserialize)]
@@ -66,17 +158,103 @@ pub async fn post_claim_record_handler(
     // Compute payout to store in DB (same formula as on-chain)
     let payout = compute_user_payout(&state.pool, &body.wallet, body.market_id)
         .await
-        .unwrap_or(0);
+        .unwrap_or_else(|_| UserPayout::none());
 
     // Mark bet claimed in DB
-    let _ = mark_bet_claimed(&state.pool, &body.wallet, body.market_id, payout).await;
+    let _ = mark_bet_claimed(&state.pool, &body.wallet, body.market_id, payout.lamports).await;
 
     // Record payout entry (optional but recommended)
-    let _ = record_payout(&state.pool, &body.wallet, body.market_id, payout, &body.tx_sig).await;
+    let _ = record_payout(&state.pool, &body.wallet, body.market_id, payout.lamports, &body.tx_sig).await;
+
+    // The claim took its protocol fee on-chain
+    let sol = state.sol.clone();
+    let market_id = body.market_id;
+    if let Ok(Ok(market)) = tokio::task::spawn_blocking(move || sol.fetch_market(market_id as u64)).await {
+        let _ = update_market_fees(&state.pool, market_id, market.fee_bps, market.fees_accrued).await;
+    }
+
+    Json(json!({
+        "ok": true,
+        "payout": payout.lamports,
+        "kind": payout.kind,
+        "tx_sig": body.tx_sig
+    }))
+}
+
+//
+// ----------------------------------------------------------
+//  POST /claim/withdraw/record
+// ----------------------------------------------------------
+//  Body: same as /claim/record
+//
+//  After user signs and submits on-chain withdraw_bet (before
+//  lock), backend checks that the tx confirmed and the bet's
+//  account is gone, then marks the bet withdrawn with its refund
+//  and exit penalty and refreshes the market's pools and fees
+//  from chain.
+//
+//  Returns:
+//  { ok: true, refund: base units, penalty: base units }
+// ----------------------------------------------------------
+//
+pub async fn post_withdraw_record_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<ClaimRecordBody>,
+) -> Json<serde_json::Value> {
+    let wallet = match Pubkey::from_str(&body.wallet) {
+        Ok(w) => w,
+        Err(e) => return Json(json!({ "ok": false, "error": format!("invalid wallet: {}", e) })),
+    };
+
+    // The penalty rate is snapshotted per market, so read it from chain
+    let sol = state.sol.clone();
+    let market_id = body.market_id;
+    let tx_sig = body.tx_sig.clone();
+    let checked = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let market = sol.fetch_market(market_id as u64)?;
+
+        // Only record a withdrawal the program has actually made
+        if !sol.signature_confirmed(&tx_sig)? {
+            anyhow::bail!("withdraw tx {} is not confirmed", tx_sig);
+        }
+        let (market_pda, _) = sol.derive_market_pda(market_id as u64);
+        let (bet_pda, _) = sol.derive_bet_pda(&wallet, &market_pda);
+        if sol.account_exists(&bet_pda)? {
+            anyhow::bail!("bet {} is still open on-chain", bet_pda);
+        }
+
+        Ok(market)
+    })
+    .await;
+
+    let market = match checked {
+        Ok(Ok(m)) => m,
+        Ok(Err(e)) => return Json(json!({ "ok": false, "error": e.to_string() })),
+        Err(e) => return Json(json!({ "ok": false, "error": format!("{:?}", e) })),
+    };
+
+    let (refund, penalty) =
+        match mark_bet_withdrawn(&state.pool, &body.wallet, market_id, market.exit_penalty_bps).await {
+            Ok(amounts) => amounts,
+            Err(e) => return Json(json!({ "ok": false, "error": e.to_string() })),
+        };
+
+    let _ = update_market_pools(
+        &state.pool,
+        market_id,
+        market.green_pool_weighted,
+        market.red_pool_weighted,
+        &market.bucket_pools_weighted,
+    )
+    .await;
+    let _ = update_market_fees(&state.pool, market_id, market.fee_bps, market.fees_accrued).await;
+
+    let _ = record_payout(&state.pool, &body.wallet, market_id, refund as i64, &body.tx_sig).await;
 
     Json(json!({
         "ok": true,
-        "payout": payout,
+        "refund": refund,
+        "penalty": penalty,
         "tx_sig": body.tx_sig
     }))
 }
@@ -89,5 +267,7 @@ pub fn routes() -> axum::Router<Arc<AppState>> {
 
     axum::Router::new()
         .route("/claimable/:market_id/:wallet", get(get_claimable_handler))
+        .route("/claimable/:wallet", get(get_all_claimable_handler))
         .route("/claim/record", post(post_claim_record_handler))
+        .route("/claim/withdraw/record", post(post_withdraw_record_handler))
 }
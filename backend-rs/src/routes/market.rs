@@ -10,12 +10,15 @@ use chrono::{Utc, TimeZone};
 
 use crate::state::AppState;
 use crate::repository::{
+    get_asset,
+    get_enabled_assets,
     get_market_from_db,
     get_active_markets,
     get_user_pnl,
     insert_market,
 };
 use crate::oracle::get_latest_candle;
+use candle_markets::state::{MarketParams, NATIVE_SOL_DECIMALS};
 
 /// ---------------------------------------------------------------------------
 /// MARKET ROUTES
@@ -23,6 +26,7 @@ use crate::oracle::get_latest_candle;
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/active", get(get_active_markets_handler))
+        .route("/assets", get(get_assets_handler))
         .route("/:id", get(get_market_handler))
         .route("/pnl/:wallet", get(get_pnl_handler))
         .route("/force-create", post(force_create_market_handler)) // DEV ONLY
@@ -40,6 +44,19 @@ async fn get_active_markets_handler(
     }
 }
 
+/// ---------------------------------------------------------------------------
+/// GET /market/assets
+/// ---------------------------------------------------------------------------
+/// Symbols new markets can be created for, from the on-chain registry.
+async fn get_assets_handler(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    match get_enabled_assets(&state.pool).await {
+        Ok(assets) => Json(json!(assets)),
+        Err(e) => Json(json!({ "error": e.to_string() })),
+    }
+}
+
 /// ---------------------------------------------------------------------------
 /// GET /market/:id
 /// ---------------------------------------------------------------------------
@@ -72,11 +89,19 @@ async fn get_pnl_handler(
 async fn force_create_market_handler(
     State(state): State<Arc<AppState>>,
 ) -> Json<serde_json::Value> {
-    let asset = "BTC/USDT";
     tracing::info!("[FORCE CREATE] Starting forced market creation...");
 
+    let asset = match get_asset(&state.pool, &state.market_asset).await {
+        Ok(a) if a.enabled => a,
+        Ok(_) => return Json(json!({ "ok": false, "error": "asset is disabled" })),
+        Err(e) => {
+            tracing::error!("[FORCE CREATE] Asset {} not registered: {:?}", state.market_asset, e);
+            return Json(json!({ "ok": false, "error": e.to_string() }));
+        }
+    };
+
     // 1. Fetch latest 4h candle
-    let candle = match get_latest_candle(4).await {
+    let candle = match get_latest_candle(&asset.symbol, 4).await {
         Ok(c) => c,
         Err(e) => {
             tracing::error!("[FORCE CREATE] Oracle error: {:?}", e);
@@ -89,7 +114,8 @@ async fn force_create_market_handler(
     // 2. Compute times
     let start_time = candle.timestamp as i64;
     let end_time = start_time + 4 * 3600;
-    let lock_time = end_time - 600;
+    let params = MarketParams::default();
+    let lock_time = end_time - params.lock_offset;
 
     // ✅ Deterministic market_id (shared with scheduler & Solana)
     let market_id = start_time;
@@ -113,11 +139,19 @@ async fn force_create_market_handler(
     let db_row_id = match insert_market(
         &state.pool,
         market_id,
-        asset,
+        &asset.symbol,
         Utc.timestamp_opt(start_time, 0).unwrap(),
         Utc.timestamp_opt(end_time, 0).unwrap(),
         Utc.timestamp_opt(lock_time, 0).unwrap(),
         open_price,
+        None,
+        NATIVE_SOL_DECIMALS as i16,
+        params.virtual_liquidity,
+        &params.bucket_bounds,
+        None,
+        None,
+        None,
+        None,
     )
     .await {
         Ok(id) => id,
@@ -135,14 +169,17 @@ async fn force_create_market_handler(
 
     // 5. Create on-chain market
     let sol = state.sol.clone();
-    let on_chain_price = (open_price * 100.0) as u64;
+    let on_chain_price = asset.to_on_chain_price(open_price);
 
     match tokio::task::spawn_blocking(move || {
         sol.create_market_and_send(
+            &asset.symbol,
             on_chain_price,
             start_time,
             end_time,
             market_id as u64,
+            None,
+            &params,
         )
     })
     .await {
@@ -11,6 +11,7 @@ pub mod pnl;
 pub mod claim;
 pub mod treasury;
 pub mod prices;
+pub mod admin;
 
 // Build router (but we no longer use this — main.rs merges manually)
 pub fn create_router(state: Arc<AppState>) -> Router {
@@ -24,6 +25,7 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .nest("/claim", claim::routes())
         .nest("/treasury", treasury::treasury_routes())
         .nest("/prices", prices::routes())
+        .nest("/admin", admin::routes())
         .with_state(state)
 }
 
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use serde::Deserialize;
 
 use crate::state::AppState;
-use crate::oracle::{get_latest_candle, binance::fetch_binance_historical};
+use crate::oracle::{binance_symbol, get_latest_candle, fetch_binance_historical};
 
 #[derive(Deserialize)]
 struct HistoricalParams {
@@ -17,11 +17,13 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/:symbol/historical", get(historical_handler))
 }
 
+/// GET /oracle/:symbol
+/// Latest 4h candle; `symbol` as BTCUSDT, btc-usdt, ...
 async fn oracle_handler(Path(symbol): Path<String>) -> Json<serde_json::Value> {
-    match get_latest_candle(4).await {
+    match get_latest_candle(&symbol, 4).await {
         Ok(candle) => Json(json!({
-            "requested_symbol": symbol,   // what client requested
-            "actual_symbol": "BTC/USDT",  // we only support BTC
+            "requested_symbol": &symbol,                 // what client requested
+            "actual_symbol": binance_symbol(&symbol),    // what Binance was asked for
             "open": candle.open,
             "high": candle.high,
             "low":  candle.low,
@@ -42,7 +44,7 @@ async fn historical_handler(
 ) -> Json<serde_json::Value> {
     let limit = params.limit.unwrap_or(200).min(1000);
     
-    match fetch_binance_historical(4, limit).await {
+    match fetch_binance_historical(&symbol, 4, limit).await {
         Ok(candles) => {
             let formatted: Vec<serde_json::Value> = candles.iter().map(|c| {
                 json!({
@@ -55,8 +57,8 @@ async fn historical_handler(
             }).collect();
             
             Json(json!({
-                "requested_symbol": symbol,
-                "actual_symbol": "BTC/USDT",
+                "requested_symbol": &symbol,
+                "actual_symbol": binance_symbol(&symbol),
                 "interval": "4h",
                 "candles": formatted
             }))
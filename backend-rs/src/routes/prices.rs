@@ -1,20 +1,35 @@
-use axum::{Router, routing::get, Json};
+use axum::{Router, routing::get, extract::{Path, State}, Json};
 use serde_json::json;
 use std::sync::Arc;
 
 use crate::state::AppState;
-use crate::oracle::binance::fetch_binance_candle;
+use crate::repository::get_enabled_assets;
+use crate::oracle::binance::{binance_symbol, fetch_binance_candle};
 
 pub fn routes() -> Router<Arc<AppState>> {
-    Router::new().route("/BTCUSDT", get(get_btc_price))
+    Router::new().route("/:symbol", get(get_price))
 }
 
-/// Unified price endpoint for frontend TopBar
-async fn get_btc_price() -> Json<serde_json::Value> {
+/// Unified price endpoint for frontend TopBar. `symbol` is a registered
+/// asset in either form ("BTC/USDT" URL-encoded, or "BTCUSDT").
+async fn get_price(
+    Path(symbol): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let assets = match get_enabled_assets(&state.pool).await {
+        Ok(a) => a,
+        Err(e) => return Json(json!({ "error": e.to_string() })),
+    };
+    let asset = match assets.into_iter().find(|a| binance_symbol(&a.symbol) == binance_symbol(&symbol)) {
+        Some(a) => a,
+        None => return Json(json!({ "error": format!("unknown asset: {}", symbol) })),
+    };
+
     // Use the same 4-hour interval your backend uses everywhere
-    match fetch_binance_candle(4).await {
+    match fetch_binance_candle(&asset.symbol, 4).await {
         Ok(candle) => Json(json!({
-            "asset": "BTCUSDT",
+            "asset": binance_symbol(&asset.symbol),
+            "symbol": asset.symbol,
             "price": candle.close,
             "timestamp": candle.timestamp
         })),
@@ -5,26 +5,121 @@ use std::sync::Arc;
 use sqlx::{Pool, Postgres};
 
 // INTERNAL IMPORTS
-use crate::oracle::get_latest_candle;
+use crate::constants::{
+    HEAD_TO_HEAD_MARKET_ID_OFFSET, HEAD_TO_HEAD_RIVALS, LADDER_MARKET_ID_OFFSET,
+    LADDER_STRIKE_OFFSETS_BPS, RANGE_BUCKET_BOUNDS_BPS, RANGE_MARKET_ID_OFFSET,
+    VOLATILITY_MARKET_ID_OFFSET, VOLATILITY_THRESHOLDS_BPS,
+};
+use crate::oracle::{get_candle_at, get_latest_candle};
+use candle_markets::state::{MarketParams, RivalAsset, NATIVE_SOL_DECIMALS, MARKET_CLOSE_GRACE};
 use crate::solana_client::SolanaClient;
 use crate::repository::{
+    get_asset,
+    upsert_asset,
     insert_market,
     update_market_settlement,
+    update_market_pools,
+    update_market_fees,
+    update_market_finalizes_at,
+    outcome_label,
     get_expired_unsettled_markets,
     get_active_markets,
+    get_closable_markets,
+    mark_market_closed,
 };
 
+/// What sets a scheduled market apart from the plain Green/Red one.
+#[derive(Default)]
+struct MarketVariant {
+    /// Added to the Green/Red market_id of the same candle
+    id_offset: i64,
+    /// Non-empty for a range market
+    bucket_bounds: &'static [i32],
+    /// Strike as bps away from the open; None to compare with the open
+    strike_offset_bps: Option<i32>,
+    /// Set for a volatility market
+    volatility_threshold_bps: Option<u32>,
+    /// Set for a head-to-head market
+    rival_asset: Option<&'static str>,
+}
+
+/// ---------------------------------------------------------------------------
+/// CREATE MARKETS JOB
+/// ---------------------------------------------------------------------------
+/// The Green/Red market for the new candle of `market_asset`, then its
+/// range market, strike ladder, volatility and head-to-head markets when
+/// RANGE_BUCKET_BOUNDS_BPS / LADDER_STRIKE_OFFSETS_BPS /
+/// VOLATILITY_THRESHOLDS_BPS / HEAD_TO_HEAD_RIVALS are set.
+async fn create_markets_job(
+    sol: Arc<SolanaClient>,
+    pool: Pool<Postgres>,
+    market_asset: &str,
+) -> Result<()> {
+    let mut variants = vec![MarketVariant::default()];
+
+    if !RANGE_BUCKET_BOUNDS_BPS.is_empty() {
+        variants.push(MarketVariant {
+            id_offset: RANGE_MARKET_ID_OFFSET,
+            bucket_bounds: RANGE_BUCKET_BOUNDS_BPS,
+            ..MarketVariant::default()
+        });
+    }
+
+    for (i, &offset_bps) in LADDER_STRIKE_OFFSETS_BPS.iter().enumerate() {
+        variants.push(MarketVariant {
+            id_offset: LADDER_MARKET_ID_OFFSET + i as i64,
+            strike_offset_bps: Some(offset_bps),
+            ..MarketVariant::default()
+        });
+    }
+
+    for (i, &threshold_bps) in VOLATILITY_THRESHOLDS_BPS.iter().enumerate() {
+        variants.push(MarketVariant {
+            id_offset: VOLATILITY_MARKET_ID_OFFSET + i as i64,
+            volatility_threshold_bps: Some(threshold_bps),
+            ..MarketVariant::default()
+        });
+    }
+
+    for (i, &rival) in HEAD_TO_HEAD_RIVALS.iter().enumerate() {
+        variants.push(MarketVariant {
+            id_offset: HEAD_TO_HEAD_MARKET_ID_OFFSET + i as i64,
+            rival_asset: Some(rival),
+            ..MarketVariant::default()
+        });
+    }
+
+    for variant in variants {
+        create_market_job(sol.clone(), pool.clone(), market_asset, variant).await?;
+    }
+
+    Ok(())
+}
+
 /// ---------------------------------------------------------------------------
 /// CREATE MARKET JOB
 /// ---------------------------------------------------------------------------
 async fn create_market_job(
     sol: Arc<SolanaClient>,
     pool: Pool<Postgres>,
+    market_asset: &str,
+    variant: MarketVariant,
 ) -> Result<()> {
-    let asset = "BTC/USDT";
+    // Markets can only be opened for a registered, enabled asset
+    let asset = match get_asset(&pool, market_asset).await {
+        Ok(a) if a.enabled => a,
+        Ok(_) => {
+            tracing::warn!("[MARKET CREATE] Asset {} is disabled, skipping", market_asset);
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!("[MARKET CREATE] Asset {} not registered: {:?}", market_asset, e);
+            return Ok(());
+        }
+    };
 
     // 1. Fetch oracle candle (4h interval)
-    let candle = match get_latest_candle(4).await {
+    let candle = match get_latest_candle(&asset.symbol, 4).await {
         Ok(c) => c,
         Err(e) => {
             tracing::error!("Oracle error, skipping market creation: {:?}", e);
@@ -32,28 +127,70 @@ async fn create_market_job(
         }
     };
 
+    // Head-to-head: the rival must be registered too, and opens on the same candle
+    let rival = match variant.rival_asset {
+        Some(symbol) => {
+            let rival_asset = match get_asset(&pool, symbol).await {
+                Ok(a) if a.enabled => a,
+                _ => {
+                    tracing::warn!("[MARKET CREATE] Rival {} not tradable, skipping", symbol);
+                    return Ok(());
+                }
+            };
+            match get_latest_candle(symbol, 4).await {
+                Ok(c) => Some((rival_asset, c.open)),
+                Err(e) => {
+                    tracing::error!("Oracle error for rival {}, skipping: {:?}", symbol, e);
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+
     let open_price = candle.open;
+    let strike_price = variant
+        .strike_offset_bps
+        .map(|bps| open_price * (10_000 + bps) as f64 / 10_000.0);
 
     // 2. Compute times
     let start_time = candle.timestamp as i64;
     let end_time = start_time + 4 * 3600;
-    let lock_time = end_time - 10 * 60;
+    let params = MarketParams {
+        bucket_bounds: variant.bucket_bounds.to_vec(),
+        strike_price: strike_price.map(|p| asset.to_on_chain_price(p)),
+        volatility_threshold_bps: variant.volatility_threshold_bps,
+        rival: rival.as_ref().map(|(rival_asset, rival_open)| RivalAsset {
+            asset: rival_asset.symbol.clone(),
+            open_price: rival_asset.to_on_chain_price(*rival_open),
+        }),
+        ..MarketParams::default()
+    };
+    let lock_time = end_time - params.lock_offset;
 
     // 3. Deterministic market_id (restart-safe)
     // FIX: Add a large offset (e.g. 70000) to ensure we don't collide with 
     // old "Ghost Markets" from previous deployments on Devnet.
     
-    let market_id = start_time + 7000;
+    let market_id = start_time + 7000 + variant.id_offset;
 
     // 4. Insert into DB
     let db_id = match insert_market(
         &pool,
         market_id,
-        asset,
+        &asset.symbol,
         Utc.timestamp_opt(start_time, 0).unwrap(),
         Utc.timestamp_opt(end_time, 0).unwrap(),
         Utc.timestamp_opt(lock_time, 0).unwrap(),
         open_price,
+        None,
+        NATIVE_SOL_DECIMALS as i16,
+        params.virtual_liquidity,
+        &params.bucket_bounds,
+        strike_price,
+        variant.volatility_threshold_bps,
+        variant.rival_asset,
+        rival.as_ref().map(|(_, rival_open)| *rival_open),
     ).await {
         Ok(id) => id,
         Err(e) => {
@@ -67,22 +204,29 @@ async fn create_market_job(
     };
 
     tracing::info!(
-        "[MARKET CREATE] DB Market Created: db_id={} | market_id={} | open={}",
+        "[MARKET CREATE] DB Market Created: db_id={} | market_id={} | open={} | strike={:?} | buckets={:?} | volatility={:?} | rival={:?}",
         db_id,
         market_id,
-        open_price
+        open_price,
+        strike_price,
+        variant.bucket_bounds,
+        variant.volatility_threshold_bps,
+        variant.rival_asset
     );
 
     // 5. Call Solana create_market
     let sol_clone = sol.clone();
-    let on_chain_price = (open_price * 100.0) as u64;
+    let on_chain_price = asset.to_on_chain_price(open_price);
 
     let sig_res = tokio::task::spawn_blocking(move || {
         sol_clone.create_market_and_send(
+            &asset.symbol,
             on_chain_price,
             start_time,
             end_time,
             market_id as u64,
+            None,
+            &params,
         )
     })
     .await;
@@ -116,6 +260,7 @@ async fn create_market_job(
 pub async fn create_initial_market(
     sol: Arc<SolanaClient>,
     pool: Pool<Postgres>,
+    market_asset: &str,
 ) -> Result<()> {
     tracing::info!("[STARTUP] Checking for active markets...");
     
@@ -138,7 +283,25 @@ pub async fn create_initial_market(
     
     // No active market - create one now
     tracing::info!("[STARTUP] No active market found, creating one now...");
-    create_market_job(sol, pool).await
+    create_markets_job(sol, pool, market_asset).await
+}
+
+/// ---------------------------------------------------------------------------
+/// SYNC ASSETS (called on server startup)
+/// ---------------------------------------------------------------------------
+/// Copies every on-chain AssetConfig into the `assets` table.
+pub async fn sync_assets(
+    sol: Arc<SolanaClient>,
+    pool: Pool<Postgres>,
+) -> Result<usize> {
+    let assets = tokio::task::spawn_blocking(move || sol.fetch_asset_configs()).await??;
+
+    for asset in &assets {
+        upsert_asset(&pool, asset).await?;
+    }
+
+    tracing::info!("[STARTUP] Synced {} assets from chain", assets.len());
+    Ok(assets.len())
 }
 
 /// ---------------------------------------------------------------------------
@@ -174,8 +337,8 @@ async fn settle_market_job(
             market_id
         );
 
-        // 1. Fetch close candle
-        let candle = match get_latest_candle(4).await {
+        // 1. Fetch the candle the market was opened on, now closed
+        let candle = match get_candle_at(&market.asset, 4, market.start_time.timestamp()).await {
             Ok(c) => c,
             Err(e) => {
                 tracing::error!(
@@ -187,13 +350,77 @@ async fn settle_market_job(
             }
         };
 
+        // Volatility markets settle on the range: never on a missing high/low
+        if candle.high <= 0.0 || candle.low <= 0.0 {
+            tracing::error!(
+                "[SETTLEMENT] Candle for market {} has no high/low, not settling",
+                market_id
+            );
+            continue;
+        }
+
         let close_price = candle.close;
-        let on_chain_price = (close_price * 100.0) as u64;
+        let (high_price, low_price) = (candle.high, candle.low);
+        let on_chain_prices = match get_asset(&pool, &market.asset).await {
+            Ok(asset) => (
+                asset.to_on_chain_price(close_price),
+                asset.to_on_chain_price(high_price),
+                asset.to_on_chain_price(low_price),
+            ),
+            Err(e) => {
+                tracing::error!(
+                    "[SETTLEMENT] Unknown asset {} for market {}: {:?}",
+                    market.asset,
+                    market_id,
+                    e
+                );
+                continue;
+            }
+        };
 
-        // 2. Call Solana settle_market
+        // Head-to-head markets also need the rival's close over the same
+        // candle, in its own decimals
+        let (rival_close_price, on_chain_rival_close) = match &market.rival_asset {
+            Some(rival) => {
+                let rival_close = match get_candle_at(rival, 4, market.start_time.timestamp()).await {
+                    Ok(c) => c.close,
+                    Err(e) => {
+                        tracing::error!(
+                            "[SETTLEMENT] Oracle error for rival {} of market {}: {:?}",
+                            rival,
+                            market_id,
+                            e
+                        );
+                        continue;
+                    }
+                };
+                match get_asset(&pool, rival).await {
+                    Ok(asset) => (Some(rival_close), asset.to_on_chain_price(rival_close)),
+                    Err(e) => {
+                        tracing::error!(
+                            "[SETTLEMENT] Unknown rival {} for market {}: {:?}",
+                            rival,
+                            market_id,
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            None => (None, 0),
+        };
+
+        // 2. Call Solana settle_market (high/low decide volatility markets)
         let sol_clone = sol.clone();
+        let (on_chain_close, on_chain_high, on_chain_low) = on_chain_prices;
         let sig_res = tokio::task::spawn_blocking(move || {
-            sol_clone.settle_market_and_send(market_id as u64, on_chain_price)
+            sol_clone.settle_market_and_send(
+                market_id as u64,
+                on_chain_close,
+                on_chain_high,
+                on_chain_low,
+                on_chain_rival_close,
+            )
         })
         .await;
 
@@ -205,12 +432,59 @@ async fn settle_market_job(
                     sig
                 );
 
-                // 3. Update DB
+                // 3. Read back the on-chain outcome (void markets refund everyone)
+                let sol_clone = sol.clone();
+                let onchain = tokio::task::spawn_blocking(move || {
+                    sol_clone.fetch_market(market_id as u64)
+                })
+                .await;
+
+                let outcome = match onchain {
+                    Ok(Ok(m)) if !m.settled => {
+                        // Vote mode: our price is in, the other settlers have yet to agree
+                        tracing::info!(
+                            "[SETTLEMENT] Close price submitted for market_id={}, waiting for quorum",
+                            market_id
+                        );
+                        continue;
+                    }
+                    Ok(Ok(m)) => {
+                        update_market_pools(
+                            &pool,
+                            market_id,
+                            m.green_pool_weighted,
+                            m.red_pool_weighted,
+                            &m.bucket_pools_weighted,
+                        )
+                        .await?;
+                        update_market_fees(&pool, market_id, m.fee_bps, m.fees_accrued).await?;
+                        update_market_finalizes_at(&pool, market_id, m.finalizes_at).await?;
+                        outcome_label(&m.outcome)
+                    }
+                    Ok(Err(e)) => {
+                        tracing::error!(
+                            "[SETTLEMENT] Failed to read market_id={} after settle: {:?}",
+                            market_id,
+                            e
+                        );
+                        "PENDING".into()
+                    }
+                    Err(e) => {
+                        tracing::error!("spawn_blocking error: {:?}", e);
+                        "PENDING".into()
+                    }
+                };
+
+                // 4. Update DB
                 update_market_settlement(
                     &pool,
                     market_id,
                     close_price,
+                    high_price,
+                    low_price,
+                    rival_close_price,
                     true,
+                    &outcome,
                 )
                 .await?;
 
@@ -233,42 +507,125 @@ async fn settle_market_job(
     Ok(())
 }
 
+/// ---------------------------------------------------------------------------
+/// CLOSE MARKETS JOB (janitor: reclaims MarketAccount rent)
+/// ---------------------------------------------------------------------------
+/// The program refuses to close a market that still has unclaimed bets,
+/// so failures here are expected and simply retried on the next run.
+async fn close_markets_job(
+    sol: Arc<SolanaClient>,
+    pool: Pool<Postgres>,
+) -> Result<()> {
+    let market_ids = get_closable_markets(&pool, MARKET_CLOSE_GRACE).await?;
+
+    if market_ids.is_empty() {
+        return Ok(());
+    }
+
+    tracing::info!("[JANITOR] {} markets eligible for closing.", market_ids.len());
+
+    for market_id in market_ids {
+        // Last chance to read the fees collected by claims before the account is gone
+        let sol_clone = sol.clone();
+        let fetch_res = tokio::task::spawn_blocking(move || {
+            sol_clone.fetch_market(market_id as u64)
+        })
+        .await;
+
+        if let Ok(Ok(m)) = fetch_res {
+            update_market_fees(&pool, market_id, m.fee_bps, m.fees_accrued).await?;
+        }
+
+        let sol_clone = sol.clone();
+        let sig_res = tokio::task::spawn_blocking(move || {
+            sol_clone.close_market_and_send(market_id as u64)
+        })
+        .await;
+
+        match sig_res {
+            Ok(Ok(sig)) => {
+                tracing::info!(
+                    "[JANITOR] Closed market_id={} tx={}",
+                    market_id,
+                    sig
+                );
+                mark_market_closed(&pool, market_id).await?;
+            }
+            Ok(Err(e)) => {
+                tracing::warn!(
+                    "[JANITOR] Market not closable yet: market_id={} err={}",
+                    market_id,
+                    e
+                );
+            }
+            Err(e) => tracing::error!("spawn_blocking error: {:?}", e),
+        }
+    }
+
+    Ok(())
+}
+
 /// ---------------------------------------------------------------------------
 /// START SCHEDULER
 /// ---------------------------------------------------------------------------
+/// With `settler_only`, only the settle job runs: the node votes on close
+/// prices for markets created by the primary backend.
 pub async fn start_scheduler(
     sol: Arc<SolanaClient>,
     pool: Pool<Postgres>,
+    market_asset: String,
+    settler_only: bool,
 ) -> Result<()> {
     let sched = JobScheduler::new().await?;
 
+    // Every 10 minutes → settle expired markets
+    let sol_clone = sol.clone();
+    let pool_clone = pool.clone();
+    let settle_job = Job::new_async("0 */10 * * * *", move |_uuid, _l| {
+        let sol = sol_clone.clone();
+        let pool = pool_clone.clone();
+        Box::pin(async move {
+            if let Err(e) = settle_market_job(sol, pool).await {
+                tracing::error!("[SCHEDULER] Settle job error: {:?}", e);
+            }
+        })
+    })?;
+    sched.add(settle_job).await?;
+
+    if settler_only {
+        sched.start().await?;
+        tracing::info!("[SCHEDULER] Settler node active (settlement votes only).");
+        return Ok(());
+    }
+
     // Every 4 hours → create new market
     let sol_clone = sol.clone();
     let pool_clone = pool.clone();
     let create_job = Job::new_async("0 0 */4 * * *", move |_uuid, _l| {
         let sol = sol_clone.clone();
         let pool = pool_clone.clone();
+        let market_asset = market_asset.clone();
         Box::pin(async move {
-            if let Err(e) = create_market_job(sol, pool).await {
+            if let Err(e) = create_markets_job(sol, pool, &market_asset).await {
                 tracing::error!("[SCHEDULER] Create job error: {:?}", e);
             }
         })
     })?;
     sched.add(create_job).await?;
 
-    // Every 10 minutes → settle expired markets
+    // Every hour → close old markets and reclaim rent
     let sol_clone = sol.clone();
     let pool_clone = pool.clone();
-    let settle_job = Job::new_async("0 */10 * * * *", move |_uuid, _l| {
+    let janitor_job = Job::new_async("0 30 * * * *", move |_uuid, _l| {
         let sol = sol_clone.clone();
         let pool = pool_clone.clone();
         Box::pin(async move {
-            if let Err(e) = settle_market_job(sol, pool).await {
-                tracing::error!("[SCHEDULER] Settle job error: {:?}", e);
+            if let Err(e) = close_markets_job(sol, pool).await {
+                tracing::error!("[SCHEDULER] Janitor job error: {:?}", e);
             }
         })
     })?;
-    sched.add(settle_job).await?;
+    sched.add(janitor_job).await?;
 
     sched.start().await?;
     tracing::info!("[SCHEDULER] BTC Market Scheduler Active.");
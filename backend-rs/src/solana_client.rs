@@ -4,21 +4,26 @@ use std::sync::Arc;
 use anyhow::{anyhow, Result};
 use anchor_client::{Client, Cluster, Program};
 use solana_sdk::{
+    bpf_loader_upgradeable,
     commitment_config::CommitmentConfig,
-    signature::{read_keypair_file, Keypair, Signer},
+    signature::{read_keypair_file, Keypair, Signature, Signer},
     pubkey::Pubkey,
     system_instruction,
     transaction::Transaction,
     instruction::{AccountMeta, Instruction},
 };
 
+use anchor_client::anchor_lang::AnchorSerialize;
+use candle_markets::state::{AssetConfig, MarketAccount, MarketParams, MarketTotals, SettlementVotes};
+
 use crate::config::AppConfig;
-use crate::constants::MARKET_ASSET;
 
 pub struct SolanaClient {
     pub program_id: Pubkey,
     pub payer: Arc<Keypair>,
     pub cluster: Cluster,
+    /// Settle through submit_close_price votes (see AppConfig)
+    pub settle_by_vote: bool,
 }
 
 impl SolanaClient {
@@ -45,7 +50,7 @@ impl SolanaClient {
         let program_id = Pubkey::from_str(&cfg.program_id)
             .map_err(|e| anyhow!("PROGRAM_ID in .env is invalid: {}", e))?;
 
-        Ok(Self { program_id, payer, cluster })
+        Ok(Self { program_id, payer, cluster, settle_by_vote: cfg.settle_by_vote })
     }
 
     pub fn program(&self) -> Program<Arc<Keypair>> {
@@ -73,28 +78,200 @@ impl SolanaClient {
         )
     }
 
+    /// ProgramData account of the upgradeable program
+    pub fn derive_program_data_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[self.program_id.as_ref()], &bpf_loader_upgradeable::id())
+    }
+
     pub fn derive_treasury_pda(&self) -> (Pubkey, u8) {
         Pubkey::find_program_address(&[b"treasury"], &self.program_id)
     }
 
+    pub fn derive_config_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"config"], &self.program_id)
+    }
+
+    pub fn derive_settler_set_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"settlers"], &self.program_id)
+    }
+
+    pub fn derive_asset_pda(&self, symbol: &str) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"asset", symbol.as_bytes()], &self.program_id)
+    }
+
+    pub fn derive_votes_pda(&self, market_pda: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"votes", market_pda.as_ref()], &self.program_id)
+    }
+
+    /// Escrows the market's SOL; also the authority of its token vault.
+    pub fn derive_market_vault_pda(&self, market_pda: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"vault", market_pda.as_ref()], &self.program_id)
+    }
+
+    pub fn derive_market_token_vault_pda(&self, market_pda: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"market_token_vault", market_pda.as_ref()], &self.program_id)
+    }
+
+    /// Shared token vault of a mint, where SPL markets escrowed their
+    /// stakes before each market had its own.
+    pub fn derive_legacy_token_vault_pda(&self, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"token_vault", mint.as_ref()], &self.program_id)
+    }
+
+    pub fn derive_fee_vault_pda(&self, mint: Option<&Pubkey>) -> (Pubkey, u8) {
+        match mint {
+            Some(mint) => Pubkey::find_program_address(&[b"fee_vault", mint.as_ref()], &self.program_id),
+            None => Pubkey::find_program_address(&[b"fee_vault"], &self.program_id),
+        }
+    }
+
+    fn token_program_id() -> Pubkey {
+        Pubkey::from_str("TokenkegQfeZyiNwAJbNbGJPyNGG3DJzMZAhp8yz").unwrap()
+    }
+
+    // -----------------------------------------------------------
+    // FETCH MARKET ACCOUNT
+    // -----------------------------------------------------------
+    pub fn fetch_market(&self, market_id: u64) -> Result<MarketAccount> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+
+        self.program()
+            .account::<MarketAccount>(market_pda)
+            .map_err(|e| anyhow!("Failed to fetch market {}: {}", market_id, e))
+    }
+
+    pub fn account_exists(&self, address: &Pubkey) -> Result<bool> {
+        let account = self
+            .program()
+            .rpc()
+            .get_account_with_commitment(address, CommitmentConfig::confirmed())
+            .map_err(|e| anyhow!("Failed to fetch account {}: {}", address, e))?;
+
+        Ok(account.value.is_some())
+    }
+
+    /// Whether `tx_sig` landed and succeeded, at confirmed commitment.
+    pub fn signature_confirmed(&self, tx_sig: &str) -> Result<bool> {
+        let sig = Signature::from_str(tx_sig)
+            .map_err(|e| anyhow!("Invalid transaction signature {}: {}", tx_sig, e))?;
+
+        let status = self
+            .program()
+            .rpc()
+            .get_signature_status(&sig)
+            .map_err(|e| anyhow!("Failed to fetch status of {}: {}", tx_sig, e))?;
+
+        Ok(matches!(status, Some(Ok(()))))
+    }
+
+    // -----------------------------------------------------------
+    // FETCH ASSET REGISTRY (every AssetConfig PDA)
+    // -----------------------------------------------------------
+    pub fn fetch_asset_configs(&self) -> Result<Vec<AssetConfig>> {
+        let assets = self
+            .program()
+            .accounts::<AssetConfig>(vec![])
+            .map_err(|e| anyhow!("Failed to fetch asset configs: {}", e))?;
+
+        Ok(assets.into_iter().map(|(_, asset)| asset).collect())
+    }
+
+    pub fn fetch_asset_config(&self, symbol: &str) -> Result<AssetConfig> {
+        let (asset_pda, _) = self.derive_asset_pda(symbol);
+
+        self.program()
+            .account::<AssetConfig>(asset_pda)
+            .map_err(|e| anyhow!("Failed to fetch asset {}: {}", symbol, e))
+    }
+
+    // -----------------------------------------------------------
+    // ASSET REGISTRY (admin: register / update a tradable symbol)
+    // -----------------------------------------------------------
+    pub fn register_asset_and_send(
+        &self,
+        symbol: &str,
+        price_decimals: u8,
+        price_feed: Pubkey,
+    ) -> Result<String> {
+        let (asset_pda, _) = self.derive_asset_pda(symbol);
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![21, 80, 155, 149, 117, 207, 235, 16];
+        data.extend_from_slice(&(symbol.len() as u32).to_le_bytes());
+        data.extend_from_slice(symbol.as_bytes());
+        data.push(price_decimals);
+        data.extend_from_slice(price_feed.as_ref());
+
+        let accounts = vec![
+            AccountMeta::new(asset_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(Self::system_program_id(), false),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "register_asset")
+    }
+
+    pub fn update_asset_and_send(
+        &self,
+        symbol: &str,
+        price_feed: Pubkey,
+        enabled: bool,
+    ) -> Result<String> {
+        let (asset_pda, _) = self.derive_asset_pda(symbol);
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![56, 126, 238, 138, 192, 118, 228, 172];
+        data.extend_from_slice(price_feed.as_ref());
+        data.push(enabled as u8);
+
+        let accounts = vec![
+            AccountMeta::new(asset_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "update_asset")
+    }
+
     // -----------------------------------------------------------
     // TREASURY INITIALIZATION
     // -----------------------------------------------------------
+    // The backend key becomes both admin and settler of the config PDA.
     pub fn initialize_treasury_and_send(&self) -> Result<String> {
         let (treasury_pda, _bump) = self.derive_treasury_pda();
+        let (config_pda, _) = self.derive_config_pda();
+        let (program_data_pda, _) = self.derive_program_data_pda();
 
-        let discriminator: [u8; 8] = [124, 186, 211, 195, 85, 165, 129, 166];
+        let mut data = vec![124, 186, 211, 195, 85, 165, 129, 166];
+        data.extend_from_slice(self.payer.pubkey().as_ref());
         
+        // The payer must be the program's upgrade authority
         let accounts = vec![
             AccountMeta::new(treasury_pda, false),
+            AccountMeta::new(config_pda, false),
             AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(self.program_id, false),
+            AccountMeta::new_readonly(program_data_pda, false),
             AccountMeta::new_readonly(Self::system_program_id(), false),
         ];
 
         let instruction = Instruction {
             program_id: self.program_id,
             accounts,
-            data: discriminator.to_vec(),
+            data,
         };
 
         let blockhash = self
@@ -154,21 +331,109 @@ impl SolanaClient {
         Ok(sig.to_string())
     }
 
+    // -----------------------------------------------------------
+    // TREASURY WITHDRAWAL (admin: propose, then execute after the delay)
+    // -----------------------------------------------------------
+    pub fn propose_withdrawal_and_send(&self, lamports: u64, recipient: Pubkey) -> Result<String> {
+        let (treasury_pda, _) = self.derive_treasury_pda();
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![24, 15, 80, 161, 146, 233, 1, 25];
+        data.extend_from_slice(&lamports.to_le_bytes());
+        data.extend_from_slice(recipient.as_ref());
+
+        let accounts = vec![
+            AccountMeta::new(treasury_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "propose_withdrawal")
+    }
+
+    /// `recipient` must match the one in the pending proposal.
+    pub fn execute_withdrawal_and_send(&self, recipient: Pubkey) -> Result<String> {
+        let (treasury_pda, _) = self.derive_treasury_pda();
+        let (config_pda, _) = self.derive_config_pda();
+
+        let data = vec![113, 121, 203, 232, 137, 139, 248, 249];
+
+        let accounts = vec![
+            AccountMeta::new(treasury_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "execute_withdrawal")
+    }
+
+    /// Lowers the treasury's legacy reserve to what `market_ids` still
+    /// owe. List every market from before per-market vaults whose escrow
+    /// has not been migrated yet; the program checks each has no vault.
+    pub fn release_legacy_escrow_and_send(&self, market_ids: &[u64]) -> Result<String> {
+        let (treasury_pda, _) = self.derive_treasury_pda();
+        let (config_pda, _) = self.derive_config_pda();
+
+        let data = vec![156, 169, 94, 214, 58, 201, 162, 9];
+
+        let mut accounts = vec![
+            AccountMeta::new(treasury_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+        for market_id in market_ids {
+            let (market_pda, _) = self.derive_market_pda(*market_id);
+            let (vault_pda, _) = self.derive_market_vault_pda(&market_pda);
+            accounts.push(AccountMeta::new_readonly(market_pda, false));
+            accounts.push(AccountMeta::new_readonly(vault_pda, false));
+        }
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "release_legacy_escrow")
+    }
+
     // -----------------------------------------------------------
     // CREATE MARKET
     // -----------------------------------------------------------
     pub fn create_market_and_send(
         &self,
+        asset: &str,
         open_price: u64,
         start_time: i64,
         end_time: i64,
         market_id: u64,
+        mint: Option<Pubkey>,
+        params: &MarketParams,
     ) -> Result<String> {
         let (market_pda, _) = self.derive_market_pda(market_id);
+        let (vault_pda, _) = self.derive_market_vault_pda(&market_pda);
+        let (config_pda, _) = self.derive_config_pda();
+        let (asset_pda, _) = self.derive_asset_pda(asset);
+        let rival_asset_pda = match &params.rival {
+            Some(rival) => self.derive_asset_pda(&rival.asset).0,
+            None => self.program_id,
+        };
 
         let mut data = vec![103, 226, 97, 235, 200, 188, 251, 254];
         
-        let asset = MARKET_ASSET.to_string();
         let asset_bytes = asset.as_bytes();
         data.extend_from_slice(&(asset_bytes.len() as u32).to_le_bytes());
         data.extend_from_slice(asset_bytes);
@@ -176,18 +441,48 @@ impl SolanaClient {
         data.extend_from_slice(&start_time.to_le_bytes());
         data.extend_from_slice(&end_time.to_le_bytes());
         data.extend_from_slice(&market_id.to_le_bytes());
+        params
+            .serialize(&mut data)
+            .map_err(|e| anyhow!("Failed to encode market params: {}", e))?;
 
+        // Anchor reads the program id in an optional slot as "None" (native
+        // SOL, not head-to-head)
         let accounts = vec![
             AccountMeta::new(market_pda, false),
+            AccountMeta::new(vault_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(asset_pda, false),
+            AccountMeta::new_readonly(mint.unwrap_or(self.program_id), false),
             AccountMeta::new(self.payer.pubkey(), true),
             AccountMeta::new_readonly(Self::system_program_id(), false),
+            AccountMeta::new_readonly(rival_asset_pda, false),
         ];
 
-        let instruction = Instruction {
+        let mut instructions = vec![Instruction {
             program_id: self.program_id,
             accounts,
             data,
-        };
+        }];
+
+        // SPL markets get their token vault in the same transaction
+        if let Some(mint) = mint {
+            let (token_vault_pda, _) = self.derive_market_token_vault_pda(&market_pda);
+
+            instructions.push(Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new(token_vault_pda, false),
+                    AccountMeta::new_readonly(market_pda, false),
+                    AccountMeta::new_readonly(vault_pda, false),
+                    AccountMeta::new_readonly(mint, false),
+                    AccountMeta::new_readonly(config_pda, false),
+                    AccountMeta::new(self.payer.pubkey(), true),
+                    AccountMeta::new_readonly(Self::token_program_id(), false),
+                    AccountMeta::new_readonly(Self::system_program_id(), false),
+                ],
+                data: vec![27, 154, 92, 178, 175, 29, 88, 87],
+            });
+        }
 
         let blockhash = self
             .program()
@@ -196,7 +491,7 @@ impl SolanaClient {
             .map_err(|e| anyhow!("Blockhash error: {}", e))?;
 
         let mut tx = Transaction::new_unsigned(solana_sdk::message::Message::new(
-            &[instruction],
+            &instructions,
             Some(&self.payer.pubkey()),
         ));
 
@@ -214,19 +509,42 @@ impl SolanaClient {
     // -----------------------------------------------------------
     // SETTLE MARKET
     // -----------------------------------------------------------
+    /// In vote mode this only records this node's close price; the market
+    /// settles once enough settlers agree. `rival_close_price` is 0 unless
+    /// the market is head-to-head.
     pub fn settle_market_and_send(
         &self,
         market_id: u64,
         close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
     ) -> Result<String> {
+        if self.settle_by_vote {
+            return self.submit_close_price_and_send(
+                market_id,
+                close_price,
+                high_price,
+                low_price,
+                rival_close_price,
+            );
+        }
+
         let (market_pda, _) = self.derive_market_pda(market_id);
+        let (config_pda, _) = self.derive_config_pda();
+        let (settler_set_pda, _) = self.derive_settler_set_pda();
 
         let mut data = vec![193, 153, 95, 216, 166, 6, 144, 217];
         data.extend_from_slice(&close_price.to_le_bytes());
+        data.extend_from_slice(&high_price.to_le_bytes());
+        data.extend_from_slice(&low_price.to_le_bytes());
+        data.extend_from_slice(&rival_close_price.to_le_bytes());
 
         let accounts = vec![
             AccountMeta::new(market_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
             AccountMeta::new_readonly(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(settler_set_pda, false),
         ];
 
         let instruction = Instruction {
@@ -256,4 +574,544 @@ impl SolanaClient {
 
         Ok(sig.to_string())
     }
+    // -----------------------------------------------------------
+    // SUBMIT CLOSE PRICE (one settler's vote)
+    // -----------------------------------------------------------
+    pub fn submit_close_price_and_send(
+        &self,
+        market_id: u64,
+        close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
+    ) -> Result<String> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (votes_pda, _) = self.derive_votes_pda(&market_pda);
+        let (settler_set_pda, _) = self.derive_settler_set_pda();
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![40, 153, 41, 66, 68, 18, 215, 227];
+        data.extend_from_slice(&close_price.to_le_bytes());
+        data.extend_from_slice(&high_price.to_le_bytes());
+        data.extend_from_slice(&low_price.to_le_bytes());
+        data.extend_from_slice(&rival_close_price.to_le_bytes());
+
+        // The votes rent goes back to whoever opened the account; that is
+        // this settler when nobody has voted yet
+        let rent_payer = self
+            .program()
+            .account::<SettlementVotes>(votes_pda)
+            .ok()
+            .and_then(|votes| votes.rent_payer())
+            .unwrap_or_else(|| self.payer.pubkey());
+
+        let accounts = vec![
+            AccountMeta::new(market_pda, false),
+            AccountMeta::new(votes_pda, false),
+            AccountMeta::new_readonly(settler_set_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new(rent_payer, false),
+            AccountMeta::new_readonly(Self::system_program_id(), false),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "submit_close_price")
+    }
+
+    // -----------------------------------------------------------
+    // SETTLER SET (admin: M-of-N close price voting)
+    // -----------------------------------------------------------
+    pub fn set_settlers_and_send(
+        &self,
+        settlers: &[Pubkey],
+        threshold: u8,
+        tolerance_bps: u16,
+    ) -> Result<String> {
+        let (settler_set_pda, _) = self.derive_settler_set_pda();
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![224, 192, 111, 185, 154, 76, 21, 27];
+        data.extend_from_slice(&(settlers.len() as u32).to_le_bytes());
+        for settler in settlers {
+            data.extend_from_slice(settler.as_ref());
+        }
+        data.push(threshold);
+        data.extend_from_slice(&tolerance_bps.to_le_bytes());
+
+        let accounts = vec![
+            AccountMeta::new(settler_set_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(Self::system_program_id(), false),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "set_settlers")
+    }
+
+    // -----------------------------------------------------------
+    // VOID MARKET (admin: refunds every bet)
+    // -----------------------------------------------------------
+    pub fn void_market_and_send(&self, market_id: u64) -> Result<String> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (config_pda, _) = self.derive_config_pda();
+
+        let data = vec![243, 175, 46, 124, 95, 101, 39, 69];
+
+        let accounts = vec![
+            AccountMeta::new(market_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "void_market")
+    }
+
+    // -----------------------------------------------------------
+    // RE-SETTLE MARKET (admin/resolver, inside the dispute window)
+    // -----------------------------------------------------------
+    pub fn resettle_market_and_send(
+        &self,
+        market_id: u64,
+        close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
+    ) -> Result<String> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![37, 23, 93, 84, 103, 155, 208, 237];
+        data.extend_from_slice(&close_price.to_le_bytes());
+        data.extend_from_slice(&high_price.to_le_bytes());
+        data.extend_from_slice(&low_price.to_le_bytes());
+        data.extend_from_slice(&rival_close_price.to_le_bytes());
+
+        let accounts = vec![
+            AccountMeta::new(market_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "resettle_market")
+    }
+
+    // -----------------------------------------------------------
+    // DISPUTE WINDOW (admin: length in seconds + resolver key)
+    // -----------------------------------------------------------
+    pub fn set_dispute_config_and_send(&self, dispute_period: i64, resolver: Pubkey) -> Result<String> {
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![140, 9, 117, 207, 15, 127, 158, 49];
+        data.extend_from_slice(&dispute_period.to_le_bytes());
+        data.extend_from_slice(resolver.as_ref());
+
+        let accounts = vec![
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "set_dispute_config")
+    }
+
+    // -----------------------------------------------------------
+    // EMERGENCY STOP (admin: halts bets, settlement and claims)
+    // -----------------------------------------------------------
+    pub fn set_paused_and_send(&self, paused: bool) -> Result<String> {
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![91, 60, 125, 192, 176, 225, 166, 218];
+        data.push(paused as u8);
+
+        let accounts = vec![
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "set_paused")
+    }
+
+    pub fn set_market_paused_and_send(&self, market_id: u64, paused: bool) -> Result<String> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![233, 31, 161, 248, 178, 111, 102, 65];
+        data.push(paused as u8);
+
+        let accounts = vec![
+            AccountMeta::new(market_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "set_market_paused")
+    }
+
+    // -----------------------------------------------------------
+    // CLOSE MARKET (admin: reclaims MarketAccount and vault rent)
+    // -----------------------------------------------------------
+    pub fn close_market_and_send(&self, market_id: u64) -> Result<String> {
+        let market = self.fetch_market(market_id)?;
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (vault_pda, _) = self.derive_market_vault_pda(&market_pda);
+        let (fee_vault_pda, _) = self.derive_fee_vault_pda(None);
+        let (config_pda, _) = self.derive_config_pda();
+
+        let data = vec![88, 154, 248, 186, 48, 14, 123, 244];
+
+        let mut accounts = vec![
+            AccountMeta::new(market_pda, false),
+            AccountMeta::new(vault_pda, false),
+            AccountMeta::new(fee_vault_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+        ];
+
+        // Token vault accounts are optional; the program id stands for "None"
+        if market.is_native() {
+            accounts.extend([
+                AccountMeta::new_readonly(self.program_id, false),
+                AccountMeta::new_readonly(self.program_id, false),
+                AccountMeta::new_readonly(self.program_id, false),
+            ]);
+        } else {
+            let (token_vault_pda, _) = self.derive_market_token_vault_pda(&market_pda);
+            let (fee_token_vault_pda, _) = self.derive_fee_vault_pda(Some(&market.mint));
+
+            accounts.extend([
+                AccountMeta::new(token_vault_pda, false),
+                AccountMeta::new(fee_token_vault_pda, false),
+                AccountMeta::new_readonly(Self::token_program_id(), false),
+            ]);
+        }
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "close_market")
+    }
+
+    // -----------------------------------------------------------
+    // MIGRATE MARKET (admin: grow a pre-totals market, backfill totals)
+    // -----------------------------------------------------------
+    pub fn migrate_market_and_send(&self, market_id: u64, totals: &MarketTotals) -> Result<String> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![201, 113, 181, 120, 217, 60, 109, 203];
+        totals
+            .serialize(&mut data)
+            .map_err(|e| anyhow!("Failed to encode market totals: {}", e))?;
+
+        let accounts = vec![
+            AccountMeta::new(market_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(Self::system_program_id(), false),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "migrate_market")
+    }
+
+    // -----------------------------------------------------------
+    // MIGRATE ACCOUNT (anyone: any other version 0 program account)
+    // -----------------------------------------------------------
+    pub fn migrate_account_and_send(&self, account: Pubkey) -> Result<String> {
+        let data = vec![177, 228, 60, 125, 13, 116, 44, 84];
+
+        let accounts = vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(Self::system_program_id(), false),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "migrate_account")
+    }
+
+    // -----------------------------------------------------------
+    // MIGRATE MARKET ESCROW (admin: move a pre-vault market's stakes
+    // out of the treasury into its own vault)
+    // -----------------------------------------------------------
+    pub fn migrate_market_escrow_and_send(&self, market_id: u64) -> Result<String> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (vault_pda, _) = self.derive_market_vault_pda(&market_pda);
+        let (treasury_pda, _) = self.derive_treasury_pda();
+        let (config_pda, _) = self.derive_config_pda();
+
+        let data = vec![56, 242, 66, 42, 37, 183, 25, 161];
+
+        let accounts = vec![
+            AccountMeta::new_readonly(market_pda, false),
+            AccountMeta::new(vault_pda, false),
+            AccountMeta::new(treasury_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(Self::system_program_id(), false),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "migrate_market_escrow")
+    }
+
+    /// SPL markets: moves the market's tokens out of the mint's shared
+    /// vault. Runs after migrate_market_escrow created the vault authority.
+    pub fn migrate_market_token_escrow_and_send(&self, market_id: u64, mint: &Pubkey) -> Result<String> {
+        let (market_pda, _) = self.derive_market_pda(market_id);
+        let (vault_pda, _) = self.derive_market_vault_pda(&market_pda);
+        let (token_vault_pda, _) = self.derive_market_token_vault_pda(&market_pda);
+        let (legacy_vault_pda, _) = self.derive_legacy_token_vault_pda(mint);
+        let (treasury_pda, _) = self.derive_treasury_pda();
+        let (config_pda, _) = self.derive_config_pda();
+
+        let data = vec![141, 55, 92, 6, 139, 43, 180, 122];
+
+        let accounts = vec![
+            AccountMeta::new(token_vault_pda, false),
+            AccountMeta::new(legacy_vault_pda, false),
+            AccountMeta::new_readonly(market_pda, false),
+            AccountMeta::new_readonly(vault_pda, false),
+            AccountMeta::new_readonly(treasury_pda, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(self.payer.pubkey(), true),
+            AccountMeta::new_readonly(Self::token_program_id(), false),
+            AccountMeta::new_readonly(Self::system_program_id(), false),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "migrate_market_token_escrow")
+    }
+
+    // -----------------------------------------------------------
+    // PROTOCOL FEE (admin: bps of winnings for new markets)
+    // -----------------------------------------------------------
+    pub fn set_fee_bps_and_send(&self, fee_bps: u16) -> Result<String> {
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![2, 161, 245, 141, 111, 32, 39, 198];
+        data.extend_from_slice(&fee_bps.to_le_bytes());
+
+        let accounts = vec![
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "set_fee_bps")
+    }
+
+    // -----------------------------------------------------------
+    // EXIT PENALTY (admin: bps of the stake kept on withdraw_bet)
+    // -----------------------------------------------------------
+    pub fn set_exit_penalty_and_send(&self, exit_penalty_bps: u16) -> Result<String> {
+        let (config_pda, _) = self.derive_config_pda();
+
+        let mut data = vec![97, 132, 111, 220, 244, 43, 248, 203];
+        data.extend_from_slice(&exit_penalty_bps.to_le_bytes());
+
+        let accounts = vec![
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(self.payer.pubkey(), true),
+        ];
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, "set_exit_penalty")
+    }
+
+    // -----------------------------------------------------------
+    // FEE VAULT (SOL when `mint` is None, otherwise per SPL mint)
+    // -----------------------------------------------------------
+    pub fn initialize_fee_vault_and_send(&self, mint: Option<Pubkey>) -> Result<String> {
+        let (fee_vault_pda, _) = self.derive_fee_vault_pda(mint.as_ref());
+        let (config_pda, _) = self.derive_config_pda();
+
+        let (data, accounts, name) = match mint {
+            None => (
+                vec![185, 140, 228, 234, 79, 203, 252, 50],
+                vec![
+                    AccountMeta::new(fee_vault_pda, false),
+                    AccountMeta::new_readonly(config_pda, false),
+                    AccountMeta::new(self.payer.pubkey(), true),
+                    AccountMeta::new_readonly(Self::system_program_id(), false),
+                ],
+                "initialize_fee_vault",
+            ),
+            Some(mint) => {
+                let (treasury_pda, _) = self.derive_treasury_pda();
+                (
+                    vec![180, 121, 8, 58, 57, 199, 220, 114],
+                    vec![
+                        AccountMeta::new(fee_vault_pda, false),
+                        AccountMeta::new_readonly(mint, false),
+                        AccountMeta::new_readonly(treasury_pda, false),
+                        AccountMeta::new_readonly(config_pda, false),
+                        AccountMeta::new(self.payer.pubkey(), true),
+                        AccountMeta::new_readonly(Self::token_program_id(), false),
+                        AccountMeta::new_readonly(Self::system_program_id(), false),
+                    ],
+                    "initialize_token_fee_vault",
+                )
+            }
+        };
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, name)
+    }
+
+    // -----------------------------------------------------------
+    // WITHDRAW FEES (admin: fee vault -> recipient)
+    // -----------------------------------------------------------
+    /// For SPL mints `recipient` must be a token account of that mint.
+    pub fn withdraw_fees_and_send(
+        &self,
+        amount: u64,
+        recipient: Pubkey,
+        mint: Option<Pubkey>,
+    ) -> Result<String> {
+        let (fee_vault_pda, _) = self.derive_fee_vault_pda(mint.as_ref());
+        let (config_pda, _) = self.derive_config_pda();
+
+        let (mut data, accounts, name) = match mint {
+            None => (
+                vec![198, 212, 171, 109, 144, 215, 174, 89],
+                vec![
+                    AccountMeta::new(fee_vault_pda, false),
+                    AccountMeta::new_readonly(config_pda, false),
+                    AccountMeta::new(recipient, false),
+                    AccountMeta::new_readonly(self.payer.pubkey(), true),
+                ],
+                "withdraw_fees",
+            ),
+            Some(mint) => {
+                let (treasury_pda, _) = self.derive_treasury_pda();
+                (
+                    vec![164, 141, 26, 138, 241, 201, 11, 232],
+                    vec![
+                        AccountMeta::new(fee_vault_pda, false),
+                        AccountMeta::new_readonly(mint, false),
+                        AccountMeta::new(recipient, false),
+                        AccountMeta::new_readonly(treasury_pda, false),
+                        AccountMeta::new_readonly(config_pda, false),
+                        AccountMeta::new_readonly(self.payer.pubkey(), true),
+                        AccountMeta::new_readonly(Self::token_program_id(), false),
+                    ],
+                    "withdraw_fees_token",
+                )
+            }
+        };
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        };
+
+        self.send_instruction(instruction, name)
+    }
+
+    /// Signs `instruction` with the backend key and waits for confirmation.
+    fn send_instruction(&self, instruction: Instruction, name: &str) -> Result<String> {
+        let blockhash = self
+            .program()
+            .rpc()
+            .get_latest_blockhash()
+            .map_err(|e| anyhow!("Blockhash error: {}", e))?;
+
+        let mut tx = Transaction::new_unsigned(solana_sdk::message::Message::new(
+            &[instruction],
+            Some(&self.payer.pubkey()),
+        ));
+
+        tx.sign(&[&*self.payer], blockhash);
+
+        let sig = self
+            .program()
+            .rpc()
+            .send_and_confirm_transaction(&tx)
+            .map_err(|e| anyhow!("Failed to send {} tx: {}", name, e))?;
+
+        Ok(sig.to_string())
+    }
 }
\ No newline at end of file
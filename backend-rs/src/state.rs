@@ -6,4 +6,7 @@ use crate::solana_client::SolanaClient;
 pub struct AppState {
     pub sol: Arc<SolanaClient>,
     pub pool: Pool<Postgres>,
+    pub admin_api_key: Option<String>,
+    /// The asset markets are opened for (MARKET_ASSET)
+    pub market_asset: String,
 }
@@ -0,0 +1,238 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    BetSide, ClaimAmounts, ExitAmounts, MarketAccount, MarketOutcome, UserBetAccount,
+};
+
+// ====================================
+// EVENTS
+// ====================================
+//
+// Everything an indexer needs to rebuild markets and positions from logs,
+// without reading accounts.
+
+#[event]
+pub struct MarketCreated {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub asset: String,
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub open_price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub lock_time: i64,
+    pub virtual_liquidity: u64,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub fee_bps: u16,
+    /// Empty for Green/Red markets
+    pub bucket_bounds: Vec<i32>,
+    /// What the close is compared with; the open price unless given
+    pub strike_price: u64,
+    /// Non-zero for volatility markets
+    pub volatility_threshold_bps: u32,
+    /// Empty unless head-to-head
+    pub rival_asset: String,
+    pub rival_open_price: u64,
+}
+
+#[event]
+pub struct BetPlaced {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub user: Pubkey,
+    pub side: BetSide,
+    /// This bet only
+    pub amount: u64,
+    pub weight: u64,
+    pub effective_stake: u64,
+    /// The whole position after this bet
+    pub position_amount: u64,
+    pub position_effective_stake: u64,
+    pub green_pool_weighted: u64,
+    pub red_pool_weighted: u64,
+    /// Range markets only
+    pub bucket_pools_weighted: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetWithdrawn {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub user: Pubkey,
+    pub side: BetSide,
+    pub amount: u64,
+    pub effective_stake: u64,
+    pub refund: u64,
+    pub penalty: u64,
+    pub green_pool_weighted: u64,
+    pub red_pool_weighted: u64,
+    /// Range markets only
+    pub bucket_pools_weighted: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketSettled {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub close_price: u64,
+    pub high_price: u64,
+    pub low_price: u64,
+    /// Head-to-head markets only
+    pub rival_close_price: u64,
+    pub outcome: MarketOutcome,
+    pub green_pool_weighted: u64,
+    pub red_pool_weighted: u64,
+    /// Range markets only
+    pub bucket_pools_weighted: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClosePriceSubmitted {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub settler: Pubkey,
+    pub price: u64,
+    pub high_price: u64,
+    pub low_price: u64,
+    pub rival_price: u64,
+    /// Votes recorded so far, including this one
+    pub votes: u8,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub user: Pubkey,
+    pub side: BetSide,
+    pub amount: u64,
+    pub effective_stake: u64,
+    pub outcome: MarketOutcome,
+    /// Net of the fee
+    pub payout: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    /// Default pubkey for SOL
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct WithdrawalProposed {
+    pub amount: u64,
+    pub recipient: Pubkey,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct WithdrawalExecuted {
+    pub amount: u64,
+    pub recipient: Pubkey,
+}
+
+#[event]
+pub struct LegacyEscrowReleased {
+    pub released: u64,
+    /// What the treasury still keeps back for legacy markets
+    pub legacy_escrow: u64,
+}
+
+impl BetPlaced {
+    pub fn new(
+        market_key: Pubkey,
+        market: &MarketAccount,
+        user_bet: &UserBetAccount,
+        amount: u64,
+        effective_stake: u64,
+        timestamp: i64,
+    ) -> Self {
+        Self {
+            market: market_key,
+            market_id: market.market_id,
+            user: user_bet.user,
+            side: user_bet.side.clone(),
+            amount,
+            weight: market.weight_at(timestamp),
+            effective_stake,
+            position_amount: user_bet.amount,
+            position_effective_stake: user_bet.effective_stake,
+            green_pool_weighted: market.green_pool_weighted,
+            red_pool_weighted: market.red_pool_weighted,
+            bucket_pools_weighted: market.bucket_pools_weighted.clone(),
+            timestamp,
+        }
+    }
+}
+
+impl BetWithdrawn {
+    pub fn new(
+        market_key: Pubkey,
+        market: &MarketAccount,
+        user_bet: &UserBetAccount,
+        exit: &ExitAmounts,
+        timestamp: i64,
+    ) -> Self {
+        Self {
+            market: market_key,
+            market_id: market.market_id,
+            user: user_bet.user,
+            side: user_bet.side.clone(),
+            amount: user_bet.amount,
+            effective_stake: user_bet.effective_stake,
+            refund: exit.refund,
+            penalty: exit.penalty,
+            green_pool_weighted: market.green_pool_weighted,
+            red_pool_weighted: market.red_pool_weighted,
+            bucket_pools_weighted: market.bucket_pools_weighted.clone(),
+            timestamp,
+        }
+    }
+}
+
+impl MarketSettled {
+    pub fn new(market_key: Pubkey, market: &MarketAccount, timestamp: i64) -> Self {
+        Self {
+            market: market_key,
+            market_id: market.market_id,
+            close_price: market.close_price,
+            high_price: market.high_price,
+            low_price: market.low_price,
+            rival_close_price: market.rival_close_price,
+            outcome: market.outcome.clone(),
+            green_pool_weighted: market.green_pool_weighted,
+            red_pool_weighted: market.red_pool_weighted,
+            bucket_pools_weighted: market.bucket_pools_weighted.clone(),
+            timestamp,
+        }
+    }
+}
+
+impl RewardClaimed {
+    pub fn new(
+        market_key: Pubkey,
+        market: &MarketAccount,
+        user_bet: &UserBetAccount,
+        claimed: &ClaimAmounts,
+    ) -> Self {
+        Self {
+            market: market_key,
+            market_id: market.market_id,
+            user: user_bet.user,
+            side: user_bet.side.clone(),
+            amount: user_bet.amount,
+            effective_stake: user_bet.effective_stake,
+            outcome: market.outcome.clone(),
+            payout: claimed.payout,
+            fee: claimed.fee,
+        }
+    }
+}
@@ -5,8 +5,13 @@ use anchor_lang::solana_program::{
     program::invoke,
     system_instruction,
 };
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
 
+pub mod events;
+pub mod migration;
+pub mod pyth;
 pub mod state;
+use events::*;
 use state::*;
 
 declare_id!("9fAJRwzjj7dBMt7fimMo6jKwwsYFD4k9eoMPD8MwBnWb");
@@ -18,9 +23,127 @@ pub mod candle_markets {
     // ---------------------------------------------------------
     //  STEP 1 — INITIALIZE TREASURY PDA
     // ---------------------------------------------------------
-    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>, settler: Pubkey) -> Result<()> {
         let treasury = &mut ctx.accounts.treasury;
+        treasury.version = ACCOUNT_VERSION;
         treasury.bump = ctx.bumps.treasury;   // updated bumps API
+
+        // Config PDA is created alongside the treasury; the upgrade authority
+        // (checked by the context) becomes admin
+        let config = &mut ctx.accounts.config;
+        config.version = ACCOUNT_VERSION;
+        config.admin = ctx.accounts.authority.key();
+        config.settler = settler;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 2 — ROTATE ADMIN / SETTLER KEYS
+    // ---------------------------------------------------------
+    pub fn update_authorities(
+        ctx: Context<UpdateAuthorities>,
+        new_admin: Pubkey,
+        new_settler: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = new_admin;
+        config.settler = new_settler;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 2b — ASSET REGISTRY (symbols markets can trade)
+    // ---------------------------------------------------------
+    pub fn register_asset(
+        ctx: Context<RegisterAsset>,
+        symbol: String,
+        price_decimals: u8,
+        price_feed: Pubkey,
+    ) -> Result<()> {
+        require!(is_valid_symbol(&symbol), CandleError::InvalidAsset);
+        require!(price_decimals <= MAX_PRICE_DECIMALS, CandleError::InvalidAsset);
+
+        let asset = &mut ctx.accounts.asset_config;
+        asset.version = ACCOUNT_VERSION;
+        asset.symbol = symbol;
+        asset.price_decimals = price_decimals;
+        asset.price_feed = price_feed;
+        asset.enabled = true;
+        asset.bump = ctx.bumps.asset_config;
+        Ok(())
+    }
+
+    /// Swaps the oracle feed or enables/disables an asset. Price decimals
+    /// stay fixed since open markets were priced with them.
+    pub fn update_asset(ctx: Context<UpdateAsset>, price_feed: Pubkey, enabled: bool) -> Result<()> {
+        let asset = &mut ctx.accounts.asset_config;
+        asset.price_feed = price_feed;
+        asset.enabled = enabled;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 2c — EMERGENCY STOP (all markets / one market)
+    // ---------------------------------------------------------
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+        Ok(())
+    }
+
+    pub fn set_market_paused(ctx: Context<SetMarketPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.market.paused = paused;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 2d — PROTOCOL FEE (bps of winnings, new markets only)
+    // ---------------------------------------------------------
+    pub fn set_fee_bps(ctx: Context<SetFeeBps>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, CandleError::InvalidFee);
+        ctx.accounts.config.fee_bps = fee_bps;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 2e — SETTLEMENT DISPUTE WINDOW
+    // ---------------------------------------------------------
+    pub fn set_dispute_config(
+        ctx: Context<SetDisputeConfig>,
+        dispute_period: i64,
+        resolver: Pubkey,
+    ) -> Result<()> {
+        require!(
+            (0..=MAX_DISPUTE_PERIOD).contains(&dispute_period),
+            CandleError::InvalidDisputePeriod
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.dispute_period = dispute_period;
+        config.resolver = resolver;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 2f — EXIT PENALTY (bps of the stake, new markets only)
+    // ---------------------------------------------------------
+    pub fn set_exit_penalty(ctx: Context<SetExitPenalty>, exit_penalty_bps: u16) -> Result<()> {
+        require!(exit_penalty_bps <= MAX_EXIT_PENALTY_BPS, CandleError::InvalidFee);
+        ctx.accounts.config.exit_penalty_bps = exit_penalty_bps;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 3 — FEE VAULTS (SOL, and one per SPL mint)
+    // ---------------------------------------------------------
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        ctx.accounts.fee_vault.version = ACCOUNT_VERSION;
+        ctx.accounts.fee_vault.bump = ctx.bumps.fee_vault;
+        Ok(())
+    }
+
+    pub fn initialize_token_fee_vault(_ctx: Context<InitializeTokenFeeVault>) -> Result<()> {
+        // Token account is created by Anchor, owned by the treasury PDA
         Ok(())
     }
 
@@ -34,25 +157,121 @@ pub mod candle_markets {
         start_time: i64,
         end_time: i64,
         market_id: u64,
+        params: MarketParams,
     ) -> Result<()> {
         require!(end_time > start_time, CandleError::MarketClosed);
+        params.validate(start_time, end_time)?;
 
-        let lock_time = end_time - 600;
+        let lock_time = end_time - params.lock_offset;
+        ctx.accounts.vault.version = ACCOUNT_VERSION;
+        ctx.accounts.vault.bump = ctx.bumps.vault;
         let market = &mut ctx.accounts.market;
 
-        market.asset = asset;
+        market.version = ACCOUNT_VERSION;
+        market.asset = asset.clone();
         market.market_id = market_id;
         market.start_time = start_time;
         market.end_time = end_time;
         market.lock_time = lock_time;
         market.open_price = open_price;
         market.close_price = 0;
-        market.virtual_liquidity = 100;
+        market.virtual_liquidity = params.virtual_liquidity;
+        market.weight_schedule = params.weight_schedule;
+        market.final_weight = params.final_weight;
+        market.min_bet = params.min_bet;
+        market.max_bet = params.max_bet;
+
+        // Every pool a bet can go into starts with the virtual liquidity
+        let buckets = match params.bucket_bounds.len() {
+            0 => 0,
+            bounds => bounds + 1,
+        };
+        let binary_pool = if buckets == 0 { market.virtual_liquidity } else { 0 };
+        market.green_pool_weighted = binary_pool;
+        market.red_pool_weighted = binary_pool;
+        market.bucket_bounds = params.bucket_bounds;
+        market.bucket_pools_weighted = vec![market.virtual_liquidity; buckets];
+        market.bucket_totals = vec![0; buckets];
+        market.bucket_bettors = vec![0; buckets];
+        market.strike_price = params.strike_price.unwrap_or(open_price);
+        market.volatility_threshold_bps = params.volatility_threshold_bps.unwrap_or(0);
+        market.high_price = 0;
+        market.low_price = 0;
 
-        market.green_pool_weighted = market.virtual_liquidity;
-        market.red_pool_weighted = market.virtual_liquidity;
+        // The rival must be tradable too
+        match (&params.rival, &ctx.accounts.rival_asset_config) {
+            (Some(rival), Some(rival_config)) => {
+                require!(
+                    rival_config.symbol == rival.asset,
+                    CandleError::InvalidAsset
+                );
+                require!(rival_config.enabled, CandleError::AssetDisabled);
+                market.rival_asset = rival.asset.clone();
+                market.rival_open_price = rival.open_price;
+            }
+            (Some(_), None) => return err!(CandleError::InvalidAsset),
+            (None, _) => {
+                market.rival_asset = String::new();
+                market.rival_open_price = 0;
+            }
+        }
+        market.rival_close_price = 0;
 
         market.settled = false;
+        market.outcome = MarketOutcome::Pending;
+        market.open_bets = 0;
+        market.paused = false;
+        market.fee_bps = ctx.accounts.config.fee_bps;
+        market.fees_accrued = 0;
+        market.finalizes_at = 0;
+        market.green_total = 0;
+        market.red_total = 0;
+        market.green_bettors = 0;
+        market.red_bettors = 0;
+        market.total_claimed = 0;
+        market.exit_penalty_bps = ctx.accounts.config.exit_penalty_bps;
+
+        // No mint account means a native SOL market
+        match &ctx.accounts.mint {
+            Some(mint) => {
+                market.mint = mint.key();
+                market.decimals = mint.decimals;
+            }
+            None => {
+                market.mint = Pubkey::default();
+                market.decimals = NATIVE_SOL_DECIMALS;
+            }
+        }
+
+        emit!(MarketCreated {
+            market: market.key(),
+            market_id,
+            asset,
+            mint: market.mint,
+            decimals: market.decimals,
+            open_price,
+            start_time,
+            end_time,
+            lock_time,
+            virtual_liquidity: market.virtual_liquidity,
+            min_bet: market.min_bet,
+            max_bet: market.max_bet,
+            fee_bps: market.fee_bps,
+            bucket_bounds: market.bucket_bounds.clone(),
+            strike_price: market.strike_price,
+            volatility_threshold_bps: market.volatility_threshold_bps,
+            rival_asset: market.rival_asset.clone(),
+            rival_open_price: market.rival_open_price,
+        });
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    //  STEP 4b — TOKEN VAULT FOR AN SPL MARKET
+    // ---------------------------------------------------------
+    pub fn initialize_market_token_vault(_ctx: Context<InitializeMarketTokenVault>) -> Result<()> {
+        // Token account is created by Anchor, owned by the market's vault PDA
         Ok(())
     }
 
@@ -64,62 +283,136 @@ pub mod candle_markets {
         side: BetSide,
         amount: u64,
     ) -> Result<()> {
+        let market_key = ctx.accounts.market.key();
         let market = &mut ctx.accounts.market;
         let user_bet = &mut ctx.accounts.user_bet;
         let user = &ctx.accounts.user;
-        let treasury = &ctx.accounts.treasury;
+        let vault = &ctx.accounts.vault;
 
-        let now = Clock::get()?.unix_timestamp;
-        require!(now < market.lock_time, CandleError::MarketLocked);
-        require!(!user_bet.claimed, CandleError::Unauthorized);
+        require!(market.is_native(), CandleError::WrongMint);
 
-        const MAX_BET: u64 = 50_000_000;
-        require!(amount <= MAX_BET, CandleError::InvalidBetSize);
+        let now = Clock::get()?.unix_timestamp;
+        let effective_stake =
+            market.record_bet(user_bet, user.key(), market_key, side, amount, now)?;
 
-        // Transfer SOL into Treasury PDA
-        let ix = system_instruction::transfer(&user.key(), &treasury.key(), amount);
+        // Transfer SOL into the market's vault
+        let ix = system_instruction::transfer(&user.key(), &vault.key(), amount);
         invoke(
             &ix,
             &[
                 user.to_account_info(),
-                treasury.to_account_info(),
+                vault.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
         )?;
 
-        let elapsed = now - market.start_time;
-        let weight: u64 = if elapsed < 3600 {
-            100
-        } else if elapsed < 7200 {
-            70
-        } else if elapsed < 10800 {
-            50
-        } else {
-            20
-        };
+        emit!(BetPlaced::new(market_key, market, user_bet, amount, effective_stake, now));
 
-        let effective_stake = amount
-            .checked_mul(weight).unwrap()
-            .checked_div(100).unwrap();
+        Ok(())
+    }
 
-        match side {
-            BetSide::Green => {
-                market.green_pool_weighted =
-                    market.green_pool_weighted.checked_add(effective_stake).unwrap()
-            }
-            BetSide::Red => {
-                market.red_pool_weighted =
-                    market.red_pool_weighted.checked_add(effective_stake).unwrap()
+    // ---------------------------------------------------------
+    // STEP 5b — PLACE BET (SPL token markets)
+    // ---------------------------------------------------------
+    pub fn place_bet_token(
+        ctx: Context<PlaceBetToken>,
+        side: BetSide,
+        amount: u64,
+    ) -> Result<()> {
+        let market_key = ctx.accounts.market.key();
+        let market = &mut ctx.accounts.market;
+        let user_bet = &mut ctx.accounts.user_bet;
+
+        require!(!market.is_native(), CandleError::WrongMint);
+
+        let now = Clock::get()?.unix_timestamp;
+        let effective_stake =
+            market.record_bet(user_bet, ctx.accounts.user.key(), market_key, side, amount, now)?;
+
+        // Transfer tokens into the market's token vault
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit!(BetPlaced::new(market_key, market, user_bet, amount, effective_stake, now));
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 5c — WITHDRAW BET (before lock, minus the exit penalty)
+    // ---------------------------------------------------------
+    pub fn withdraw_bet(ctx: Context<WithdrawBet>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &ctx.accounts.user_bet;
+        let vault = &ctx.accounts.vault;
+
+        require!(market.is_native(), CandleError::WrongMint);
+
+        let now = Clock::get()?.unix_timestamp;
+        let exit = market.withdraw(user_bet, now)?;
+
+        let reserved = Rent::get()?.minimum_balance(MarketVault::LEN);
+        let available = vault.to_account_info().lamports().saturating_sub(reserved);
+        require!(available >= user_bet.amount, CandleError::InsufficientFunds);
+
+        **vault.to_account_info().try_borrow_mut_lamports()? -= user_bet.amount;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += exit.refund;
+        **ctx.accounts.fee_vault.to_account_info().try_borrow_mut_lamports()? += exit.penalty;
+
+        // The bet account itself is closed to the bettor by Anchor
+        emit!(BetWithdrawn::new(market.key(), market, user_bet, &exit, now));
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 5d — WITHDRAW BET (SPL token markets)
+    // ---------------------------------------------------------
+    pub fn withdraw_bet_token(ctx: Context<WithdrawBetToken>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &ctx.accounts.user_bet;
+
+        require!(!market.is_native(), CandleError::WrongMint);
+
+        let now = Clock::get()?.unix_timestamp;
+        let exit = market.withdraw(user_bet, now)?;
+
+        let market_key = market.key();
+        let bump = [ctx.accounts.vault_authority.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault".as_ref(), market_key.as_ref(), &bump]];
+
+        for (to, amount) in [
+            (ctx.accounts.user_token.to_account_info(), exit.refund),
+            (ctx.accounts.fee_vault.to_account_info(), exit.penalty),
+        ] {
+            if amount == 0 {
+                continue;
             }
+
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to,
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
         }
 
-        user_bet.user = user.key();
-        user_bet.market = market.key();
-        user_bet.side = side;
-        user_bet.amount = amount;
-        user_bet.weight = weight;
-        user_bet.effective_stake = effective_stake;
-        user_bet.claimed = false;
+        emit!(BetWithdrawn::new(market_key, market, user_bet, &exit, now));
 
         Ok(())
     }
@@ -130,171 +423,1746 @@ pub mod candle_markets {
     pub fn settle_market(
         ctx: Context<SettleMarket>,
         close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
     ) -> Result<()> {
         let market = &mut ctx.accounts.market;
 
         let now = Clock::get()?.unix_timestamp;
-        require!(now >= market.end_time, CandleError::MarketNotEnded);
-        require!(!market.settled, CandleError::Unauthorized);
+        market.settle(
+            close_price,
+            high_price,
+            low_price,
+            rival_close_price,
+            now,
+            ctx.accounts.config.dispute_period,
+        )?;
 
-        market.close_price = close_price;
-        market.settled = true;
+        emit!(MarketSettled::new(market.key(), market, now));
 
         Ok(())
     }
 
     // ---------------------------------------------------------
-    // STEP 8 — CLAIM REWARD
+    // STEP 7 (multisig) — SETTLER SET AND CLOSE PRICE VOTES
     // ---------------------------------------------------------
-    pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+    pub fn set_settlers(
+        ctx: Context<SetSettlers>,
+        settlers: Vec<Pubkey>,
+        threshold: u8,
+        tolerance_bps: u16,
+    ) -> Result<()> {
+        require!(
+            !settlers.is_empty() && settlers.len() <= MAX_SETTLERS,
+            CandleError::InvalidSettlerSet
+        );
+        require!(
+            threshold > 0 && threshold as usize <= settlers.len(),
+            CandleError::InvalidSettlerSet
+        );
+
+        let set = &mut ctx.accounts.settler_set;
+        set.version = ACCOUNT_VERSION;
+        set.settlers = settlers;
+        set.threshold = threshold;
+        set.tolerance_bps = tolerance_bps;
+        set.bump = ctx.bumps.settler_set;
+        Ok(())
+    }
+
+    /// Records the caller's close, high and low, and the rival's close for
+    /// a head-to-head market (ignored otherwise); settles the market as
+    /// soon as enough settlers agree on the close, and on whichever of the
+    /// others the market is decided on. The votes account is then closed
+    /// to the settler who opened it.
+    pub fn submit_close_price(
+        ctx: Context<SubmitClosePrice>,
+        close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
+    ) -> Result<()> {
         let market = &mut ctx.accounts.market;
-        let user_bet = &mut ctx.accounts.user_bet;
-        let treasury = &ctx.accounts.treasury;
-        let user = &ctx.accounts.user;
+        let votes = &mut ctx.accounts.votes;
+        let set = &ctx.accounts.settler_set;
+        let settler = ctx.accounts.settler.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(now >= market.end_time, CandleError::MarketNotEnded);
+        require!(!market.settled, CandleError::Unauthorized);
+        require!(low_price <= high_price, CandleError::InvalidPriceRange);
 
-        require!(market.settled, CandleError::SettlementPending);
-        require!(!user_bet.claimed, CandleError::AlreadyClaimed);
+        votes.version = ACCOUNT_VERSION;
+        votes.market = market.key();
+        votes.bump = ctx.bumps.votes;
+        votes.record(
+            settler,
+            close_price,
+            high_price,
+            low_price,
+            rival_close_price,
+        );
 
-        let winning_side = if market.close_price > market.open_price {
-            BetSide::Green
-        } else if market.close_price < market.open_price {
-            BetSide::Red
-        } else {
-            user_bet.claimed = true;
-            return Ok(());
-        };
+        emit!(ClosePriceSubmitted {
+            market: market.key(),
+            market_id: market.market_id,
+            settler,
+            price: close_price,
+            high_price,
+            low_price,
+            rival_price: rival_close_price,
+            votes: votes.votes.len() as u8,
+        });
 
-        if user_bet.side != winning_side {
-            user_bet.claimed = true;
+        let Some(price) = votes.quorum_price(set) else {
             return Ok(());
-        }
+        };
 
-        let virtual_liq = market.virtual_liquidity;
+        // Like a Pyth settlement, markets not decided on the high and low
+        // fall back to the close when settlers disagree on them
+        let (high, low) = match votes.quorum_range(set) {
+            Some(range) => range,
+            None if market.is_volatility() => return Ok(()),
+            None => (price, price),
+        };
 
-        let (winning_pool, losing_pool) = match winning_side {
-            BetSide::Green => (market.green_pool_weighted, market.red_pool_weighted),
-            BetSide::Red => (market.red_pool_weighted, market.green_pool_weighted),
+        let rival = if market.is_head_to_head() {
+            match votes.quorum_rival_price(set) {
+                Some(rival) => rival,
+                None => return Ok(()),
+            }
+        } else {
+            0
         };
 
-        let total_winning_weighted = winning_pool.saturating_sub(virtual_liq);
-        let total_losing_weighted = losing_pool.saturating_sub(virtual_liq);
+        let dispute_period = ctx.accounts.config.dispute_period;
+        market.settle(price, high, low, rival, now, dispute_period)?;
+        emit!(MarketSettled::new(market.key(), market, now));
 
-        if total_winning_weighted == 0 || total_losing_weighted == 0 {
-            user_bet.claimed = true;
-            return Ok(());
-        }
+        votes.close(ctx.accounts.rent_payer.to_account_info())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 7a — SETTLE MARKET FROM PYTH (permissionless)
+    // ---------------------------------------------------------
+    pub fn settle_market_with_pyth(ctx: Context<SettleMarketWithPyth>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let now = Clock::get()?.unix_timestamp;
 
-        let payout = (user_bet.effective_stake as u128)
-            .checked_mul(total_losing_weighted as u128).unwrap()
-            .checked_div(total_winning_weighted as u128).unwrap() as u64;
+        // A spot price carries no candle high/low, and one feed covers one asset
+        require!(!market.is_volatility(), CandleError::InvalidPriceRange);
+        require!(!market.is_head_to_head(), CandleError::RivalPriceMissing);
 
-        if payout > 0 {
-            let treasury_lamports = **treasury.to_account_info().lamports.borrow();
-            require!(treasury_lamports >= payout, CandleError::InsufficientFunds);
+        let data = ctx.accounts.price_feed.try_borrow_data()?;
+        let price = pyth::load_price(&data)?;
 
-            **treasury.to_account_info().try_borrow_mut_lamports()? -= payout;
-            **user.to_account_info().try_borrow_mut_lamports()? += payout;
-        }
+        // The price must have been published right after the candle close...
+        require!(price.published_at_close(market.end_time), CandleError::StalePrice);
+        // ...and the publishers must agree closely enough
+        require!(price.confidence_ok(pyth::MAX_CONF_BPS), CandleError::PriceTooUncertain);
+
+        let close_price = price
+            .scaled_to(ctx.accounts.asset_config.price_decimals as i32)
+            .ok_or(CandleError::InvalidPriceAccount)?;
+
+        // No candle high/low in a spot price; only volatility markets need them
+        market.settle(
+            close_price,
+            close_price,
+            close_price,
+            0,
+            now,
+            ctx.accounts.config.dispute_period,
+        )?;
+
+        emit!(MarketSettled::new(market.key(), market, now));
 
-        user_bet.claimed = true;
         Ok(())
     }
-}
 
-// -------------------------------------------------------------
-//  ACCOUNT CONTEXTS
-// -------------------------------------------------------------
-#[derive(Accounts)]
-pub struct InitializeTreasury<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = TreasuryAccount::LEN,
-        seeds = [b"treasury".as_ref()],
-        bump
-    )]
-    pub treasury: Account<'info, TreasuryAccount>,
+    // ---------------------------------------------------------
+    // STEP 7b — VOID MARKET (admin until final, resolver in the dispute window)
+    // ---------------------------------------------------------
+    pub fn void_market(ctx: Context<VoidMarket>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let now = Clock::get()?.unix_timestamp;
+        let by_admin = ctx.accounts.authority.key() == ctx.accounts.config.admin;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        market.void(by_admin, now)?;
 
-    pub system_program: Program<'info, System>,
-}
+        emit!(MarketSettled::new(market.key(), market, now));
 
-#[derive(Accounts)]
-#[instruction(asset: String, open_price: u64, start_time: i64, end_time: i64, market_id: u64)]
-pub struct CreateMarket<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = MarketAccount::LEN,
-        seeds = [b"market".as_ref(), &market_id.to_le_bytes()],
-        bump
-    )]
-    pub market: Account<'info, MarketAccount>,
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    // ---------------------------------------------------------
+    // STEP 7c — RE-SETTLE (admin / resolver, inside the dispute window)
+    // ---------------------------------------------------------
+    pub fn resettle_market(
+        ctx: Context<ResettleMarket>,
+        close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let now = Clock::get()?.unix_timestamp;
 
-    pub system_program: Program<'info, System>,
-}
+        market.resettle(close_price, high_price, low_price, rival_close_price, now)?;
 
-#[derive(Accounts)]
-pub struct PlaceBet<'info> {
-    #[account(mut)]
-    pub market: Account<'info, MarketAccount>,
+        emit!(MarketSettled::new(market.key(), market, now));
 
-    #[account(
-        init,
-        payer = user,
-        space = UserBetAccount::LEN,
-        seeds = [
-            b"bet".as_ref(),
-            user.key().as_ref(),
-            market.key().as_ref()
-        ],
-        bump
-    )]
-    pub user_bet: Account<'info, UserBetAccount>,
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    // ---------------------------------------------------------
+    // STEP 8 — CLAIM REWARD
+    // ---------------------------------------------------------
+    pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &mut ctx.accounts.user_bet;
+        let vault = &ctx.accounts.vault;
+        let user = &ctx.accounts.user;
 
-    #[account(
-        mut,
-        seeds = [b"treasury".as_ref()],
-        bump = treasury.bump
-    )]
-    pub treasury: Account<'info, TreasuryAccount>,
+        require!(market.is_native(), CandleError::WrongMint);
 
-    pub system_program: Program<'info, System>,
-}
+        let now = Clock::get()?.unix_timestamp;
+        let claimed = market.claim(user_bet, now)?;
+        let total = claimed.payout + claimed.fee;
 
-#[derive(Accounts)]
-pub struct SettleMarket<'info> {
-    #[account(mut)]
-    pub market: Account<'info, MarketAccount>,
+        if total > 0 {
+            // Only this market's stakes are available, never its rent
+            let reserved = Rent::get()?.minimum_balance(MarketVault::LEN);
+            let available = vault.to_account_info().lamports().saturating_sub(reserved);
+            require!(available >= total, CandleError::InsufficientFunds);
 
-    pub authority: Signer<'info>,
-}
+            **vault.to_account_info().try_borrow_mut_lamports()? -= total;
+            **user.to_account_info().try_borrow_mut_lamports()? += claimed.payout;
+            **ctx.accounts.fee_vault.to_account_info().try_borrow_mut_lamports()? += claimed.fee;
+        }
 
-#[derive(Accounts)]
-pub struct ClaimReward<'info> {
-    #[account(mut)]
-    pub market: Account<'info, MarketAccount>,
+        emit!(RewardClaimed::new(market.key(), market, user_bet, &claimed));
 
-    #[account(mut)]
-    pub user_bet: Account<'info, UserBetAccount>,
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    // ---------------------------------------------------------
+    // STEP 8a — CLAIM MANY (SOL markets, one transaction)
+    // ---------------------------------------------------------
+    /// `remaining_accounts` holds `[market, user_bet, vault]` for every
+    /// market being claimed, all writable. Each market pays out of its own
+    /// vault; the bettor is credited the total once at the end.
+    pub fn claim_many<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        let fee_vault = ctx.accounts.fee_vault.to_account_info();
+        let reserved = Rent::get()?.minimum_balance(MarketVault::LEN);
+        let now = Clock::get()?.unix_timestamp;
 
-    #[account(
-        mut,
-        seeds = [b"treasury".as_ref()],
-        bump = treasury.bump
+        let accounts = ctx.remaining_accounts;
+        require!(
+            !accounts.is_empty() && accounts.len().is_multiple_of(3),
+            CandleError::InvalidClaimAccounts
+        );
+
+        let mut total_payout: u64 = 0;
+
+        for chunk in accounts.chunks(3) {
+            let (market_info, bet_info, vault_info) = (&chunk[0], &chunk[1], &chunk[2]);
+
+            let mut market = Account::<MarketAccount>::try_from(market_info)?;
+            let mut user_bet = Account::<UserBetAccount>::try_from(bet_info)?;
+
+            // Same checks Anchor runs for claim_reward, done by hand
+            let (market_pda, _) = Pubkey::find_program_address(
+                &[b"market".as_ref(), &market.market_id.to_le_bytes()],
+                &crate::ID,
+            );
+            require_keys_eq!(market_pda, market_info.key(), CandleError::WrongMarket);
+
+            let (bet_pda, _) = Pubkey::find_program_address(
+                &[b"bet".as_ref(), user.as_ref(), market_info.key.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(bet_pda, bet_info.key(), CandleError::Unauthorized);
+            require_keys_eq!(user_bet.user, user, CandleError::Unauthorized);
+            require_keys_eq!(user_bet.market, market_info.key(), CandleError::WrongMarket);
+
+            let (vault_pda, _) = Pubkey::find_program_address(
+                &[b"vault".as_ref(), market_info.key.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(vault_pda, vault_info.key(), CandleError::WrongMarket);
+
+            require!(!market.paused, CandleError::Paused);
+            require!(market.is_native(), CandleError::WrongMint);
+
+            let claimed = market.claim(&mut user_bet, now)?;
+            let total = claimed.payout + claimed.fee;
+
+            if total > 0 {
+                let available = vault_info.lamports().saturating_sub(reserved);
+                require!(available >= total, CandleError::InsufficientFunds);
+
+                **vault_info.try_borrow_mut_lamports()? -= total;
+                **fee_vault.try_borrow_mut_lamports()? += claimed.fee;
+            }
+
+            total_payout = total_payout
+                .checked_add(claimed.payout)
+                .ok_or(CandleError::MathOverflow)?;
+
+            emit!(RewardClaimed::new(market_info.key(), &market, &user_bet, &claimed));
+
+            // Persist now so a market listed twice fails as AlreadyClaimed
+            market.exit(&crate::ID)?;
+            user_bet.exit(&crate::ID)?;
+        }
+
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += total_payout;
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 8b — CLAIM REWARD (SPL token markets)
+    // ---------------------------------------------------------
+    pub fn claim_reward_token(ctx: Context<ClaimRewardToken>) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &mut ctx.accounts.user_bet;
+
+        require!(!market.is_native(), CandleError::WrongMint);
+
+        let now = Clock::get()?.unix_timestamp;
+        let claimed = market.claim(user_bet, now)?;
+        require!(
+            ctx.accounts.vault.amount >= claimed.payout + claimed.fee,
+            CandleError::InsufficientFunds
+        );
+
+        // The market's vault PDA is the token vault's authority
+        let market_key = market.key();
+        let bump = [ctx.accounts.vault_authority.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault".as_ref(), market_key.as_ref(), &bump]];
+
+        for (to, amount) in [
+            (ctx.accounts.user_token.to_account_info(), claimed.payout),
+            (ctx.accounts.fee_vault.to_account_info(), claimed.fee),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to,
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+
+        emit!(RewardClaimed::new(market.key(), market, user_bet, &claimed));
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 9 — CLOSE BET (bettor reclaims rent)
+    // ---------------------------------------------------------
+    pub fn close_bet(ctx: Context<CloseBet>) -> Result<()> {
+        let market_info = ctx.accounts.market.to_account_info();
+
+        // Market already closed by the admin: the bet can always be cleaned up
+        if market_info.data_is_empty() {
+            return Ok(());
+        }
+
+        require!(ctx.accounts.user_bet.claimed, CandleError::NotClaimed);
+
+        require_keys_eq!(*market_info.owner, crate::ID, CandleError::WrongMarket);
+
+        let mut data = market_info.try_borrow_mut_data()?;
+        let mut market = MarketAccount::try_deserialize(&mut &data[..])?;
+        market.open_bets = market.open_bets.saturating_sub(1);
+        market.try_serialize(&mut &mut data[..])?;
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 10 — CLOSE MARKET (admin reclaims rent)
+    // ---------------------------------------------------------
+    pub fn close_market(ctx: Context<CloseMarket>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(market.is_final(now), CandleError::SettlementPending);
+        require!(
+            now >= market.end_time.saturating_add(MARKET_CLOSE_GRACE),
+            CandleError::MarketInUse
+        );
+
+        // Bet PDAs are keyed by market_id, which is reused if the market is
+        // re-created: any open bet would carry over into the new market
+        require!(market.open_bets == 0, CandleError::MarketInUse);
+
+        // Whatever is left in the vault (fees, exit penalties, rounding dust)
+        // goes to the protocol; the vault's rent goes back to the admin
+        if market.is_native() {
+            let vault = ctx.accounts.vault.to_account_info();
+            let reserved = Rent::get()?.minimum_balance(MarketVault::LEN);
+            let leftover = vault.lamports().saturating_sub(reserved);
+
+            **vault.try_borrow_mut_lamports()? -= leftover;
+            **ctx.accounts.fee_vault.to_account_info().try_borrow_mut_lamports()? += leftover;
+        } else {
+            let (Some(token_vault), Some(fee_token_vault), Some(token_program)) = (
+                &ctx.accounts.token_vault,
+                &ctx.accounts.fee_token_vault,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(CandleError::WrongMint);
+            };
+
+            let market_key = market.key();
+            let bump = [ctx.accounts.vault.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[b"vault".as_ref(), market_key.as_ref(), &bump]];
+
+            if token_vault.amount > 0 {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        token_program.to_account_info(),
+                        Transfer {
+                            from: token_vault.to_account_info(),
+                            to: fee_token_vault.to_account_info(),
+                            authority: ctx.accounts.vault.to_account_info(),
+                        },
+                        signer_seeds,
+                    ),
+                    token_vault.amount,
+                )?;
+            }
+
+            token::close_account(CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                CloseAccount {
+                    account: token_vault.to_account_info(),
+                    destination: ctx.accounts.admin.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 11 — WITHDRAW PROTOCOL FEES (admin)
+    // ---------------------------------------------------------
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        let fee_vault = ctx.accounts.fee_vault.to_account_info();
+
+        // The vault account itself must stay rent-exempt
+        let reserved = Rent::get()?.minimum_balance(FeeVaultAccount::LEN);
+        let available = fee_vault.lamports().saturating_sub(reserved);
+        require!(amount <= available, CandleError::InsufficientFunds);
+
+        **fee_vault.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(FeesWithdrawn {
+            mint: Pubkey::default(),
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_fees_token(ctx: Context<WithdrawFeesToken>, amount: u64) -> Result<()> {
+        require!(amount <= ctx.accounts.fee_vault.amount, CandleError::InsufficientFunds);
+
+        let bump = [ctx.accounts.treasury.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[b"treasury".as_ref(), &bump]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.fee_vault.to_account_info(),
+                    to: ctx.accounts.recipient_token.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        emit!(FeesWithdrawn {
+            mint: ctx.accounts.mint.key(),
+            recipient: ctx.accounts.recipient_token.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 12 — TREASURY WITHDRAWAL (admin, timelocked)
+    // ---------------------------------------------------------
+    /// Replaces any pending proposal; an `amount` of 0 cancels it.
+    pub fn propose_withdrawal(
+        ctx: Context<ProposeWithdrawal>,
+        amount: u64,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        let executable_at = Clock::get()?.unix_timestamp + WITHDRAWAL_DELAY;
+
+        treasury.pending_amount = amount;
+        treasury.pending_recipient = recipient;
+        treasury.pending_executable_at = executable_at;
+
+        emit!(WithdrawalProposed { amount, recipient, executable_at });
+
+        Ok(())
+    }
+
+    pub fn execute_withdrawal(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        let now = Clock::get()?.unix_timestamp;
+
+        // New markets escrow in their own vaults; legacy markets' stakes
+        // stay reserved here until migrate_market_escrow moves them out
+        let info = treasury.to_account_info();
+        let reserved = Rent::get()?.minimum_balance(TreasuryAccount::LEN);
+        let amount = treasury.take_withdrawal(info.lamports().saturating_sub(reserved), now)?;
+
+        **info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(WithdrawalExecuted {
+            amount,
+            recipient: ctx.accounts.recipient.key(),
+        });
+
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // STEP 13 — MIGRATE ACCOUNTS TO THE CURRENT LAYOUT
+    // ---------------------------------------------------------
+    /// Upgrades a market to the current layout (admin). Version 0 markets
+    /// also get their raw totals, open bets and bet limits filled in from
+    /// `totals`, see migration::backfill_market.
+    pub fn migrate_market(ctx: Context<MigrateMarket>, totals: MarketTotals) -> Result<()> {
+        let info = ctx.accounts.market.to_account_info();
+
+        let from_version = migration::upgrade_in_place(
+            &info,
+            migration::MARKET_V0_LEN,
+            MarketAccount::LEN,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        if from_version > 0 {
+            return Ok(());
+        }
+
+        let mut data = info.try_borrow_mut_data()?;
+        let mut market = MarketAccount::try_deserialize(&mut &data[..])?;
+        migration::backfill_market(&mut market, &totals)?;
+        market.try_serialize(&mut &mut data[..])?;
+
+        Ok(())
+    }
+
+    /// Upgrades any other account in place. Nothing but the layout
+    /// changes, so anyone may pay for it. A version 0 treasury predates
+    /// per-market vaults and still holds every legacy market's stakes, so
+    /// all of its balance is reserved until their escrow is migrated or
+    /// release_legacy_escrow lowers the reserve.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let info = ctx.accounts.account.to_account_info();
+
+        let (v0_len, len, is_treasury) = {
+            let data = info.try_borrow_data()?;
+            require!(data.len() >= 8, CandleError::UnknownAccount);
+            let (v0_len, len) = migration::layout(&data[..8]).ok_or(CandleError::UnknownAccount)?;
+            (v0_len, len, &data[..8] == TreasuryAccount::DISCRIMINATOR)
+        };
+
+        let from_version = migration::upgrade_in_place(
+            &info,
+            v0_len,
+            len,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        if is_treasury && from_version == 0 {
+            let reserved = Rent::get()?.minimum_balance(TreasuryAccount::LEN);
+            let mut data = info.try_borrow_mut_data()?;
+            let mut treasury = TreasuryAccount::try_deserialize(&mut &data[..])?;
+            treasury.legacy_escrow = info.lamports().saturating_sub(reserved);
+            treasury.try_serialize(&mut &mut data[..])?;
+        }
+
+        Ok(())
+    }
+
+    /// Gives a market from before per-market vaults its vault and moves
+    /// what it still owes its bettors there out of the treasury, which
+    /// held every market's stakes until then (admin, once per market).
+    /// SPL markets then move their tokens with migrate_market_token_escrow.
+    pub fn migrate_market_escrow(ctx: Context<MigrateMarketEscrow>) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let vault = &mut ctx.accounts.vault;
+
+        vault.version = ACCOUNT_VERSION;
+        vault.bump = ctx.bumps.vault;
+
+        if !market.is_native() {
+            return Ok(());
+        }
+
+        let owed = market.legacy_escrow()?;
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let reserved = Rent::get()?.minimum_balance(TreasuryAccount::LEN);
+        let available = treasury.lamports().saturating_sub(reserved);
+        require!(available >= owed, CandleError::InsufficientFunds);
+
+        **treasury.try_borrow_mut_lamports()? -= owed;
+        **vault.to_account_info().try_borrow_mut_lamports()? += owed;
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.legacy_escrow = treasury.legacy_escrow.saturating_sub(owed);
+
+        Ok(())
+    }
+
+    /// Opens the token vault of an SPL market from before per-market
+    /// vaults and moves what the market still owes out of the mint's
+    /// shared token vault (admin, once per market).
+    pub fn migrate_market_token_escrow(ctx: Context<MigrateMarketTokenEscrow>) -> Result<()> {
+        let legacy_vault = &ctx.accounts.legacy_vault;
+        let owed = ctx.accounts.market.legacy_escrow()?;
+        require!(legacy_vault.amount >= owed, CandleError::InsufficientFunds);
+
+        if owed > 0 {
+            let bump = [ctx.accounts.treasury.bump];
+            let signer_seeds: &[&[&[u8]]] = &[&[b"treasury".as_ref(), &bump]];
+
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: legacy_vault.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: ctx.accounts.treasury.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                owed,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Lowers the treasury's legacy reserve to what the markets from
+    /// before per-market vaults still owe their bettors (admin).
+    /// `remaining_accounts` holds `[market, vault]` for every SOL market
+    /// migrate_market_escrow has not moved yet; a market that already has
+    /// its vault is rejected. The reserve never grows back.
+    pub fn release_legacy_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReleaseLegacyEscrow<'info>>,
+    ) -> Result<()> {
+        let accounts = ctx.remaining_accounts;
+        require!(
+            accounts.len().is_multiple_of(2),
+            CandleError::InvalidLegacyMarkets
+        );
+
+        let mut still_owed: u64 = 0;
+
+        for pair in accounts.chunks(2) {
+            let (market_info, vault_info) = (&pair[0], &pair[1]);
+            let market = Account::<MarketAccount>::try_from(market_info)?;
+
+            let (vault_pda, _) = Pubkey::find_program_address(
+                &[b"vault".as_ref(), market_info.key.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(vault_pda, vault_info.key(), CandleError::WrongMarket);
+            require!(
+                vault_info.data_is_empty(),
+                CandleError::InvalidLegacyMarkets
+            );
+
+            // SPL stakes sit in the mint's token vault, not in lamports here
+            if market.is_native() {
+                still_owed = still_owed
+                    .checked_add(market.legacy_escrow()?)
+                    .ok_or(CandleError::MathOverflow)?;
+            }
+        }
+
+        let treasury = &mut ctx.accounts.treasury;
+        let released = treasury.release_legacy_escrow(still_owed);
+
+        emit!(LegacyEscrowReleased {
+            released,
+            legacy_escrow: treasury.legacy_escrow,
+        });
+
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------
+//  ACCOUNT CONTEXTS
+// -------------------------------------------------------------
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = TreasuryAccount::LEN,
+        seeds = [b"treasury".as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, TreasuryAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = ConfigAccount::LEN,
+        seeds = [b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    /// Only the program's upgrade authority may claim the admin role
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::CandleMarkets>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ CandleError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAuthorities<'info> {
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(asset: String, open_price: u64, start_time: i64, end_time: i64, market_id: u64)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = MarketAccount::LEN,
+        seeds = [b"market".as_ref(), &market_id.to_le_bytes()],
+        bump
+    )]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = MarketVault::LEN,
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, MarketVault>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    /// Only registered, enabled symbols can be traded
+    #[account(
+        seeds = [b"asset".as_ref(), asset.as_bytes()],
+        bump = asset_config.bump,
+        constraint = asset_config.enabled @ CandleError::AssetDisabled
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    /// Omit for a native SOL market
+    pub mint: Option<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Head-to-head markets only: the rival's registered asset
+    pub rival_asset_config: Option<Account<'info, AssetConfig>>,
+}
+
+#[derive(Accounts)]
+#[instruction(symbol: String)]
+pub struct RegisterAsset<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = AssetConfig::LEN,
+        seeds = [b"asset".as_ref(), symbol.as_bytes()],
+        bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAsset<'info> {
+    #[account(
+        mut,
+        seeds = [b"asset".as_ref(), asset_config.symbol.as_bytes()],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMarketPaused<'info> {
+    #[account(mut)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetExitPenalty<'info> {
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeBps<'info> {
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarketTokenVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"market_token_vault".as_ref(), market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_authority
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(constraint = market.mint == mint.key() @ CandleError::WrongMint)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault_authority.bump
+    )]
+    pub vault_authority: Account<'info, MarketVault>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDisputeConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = FeeVaultAccount::LEN,
+        seeds = [b"fee_vault".as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVaultAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTokenFeeVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"fee_vault".as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBet<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserBetAccount::LEN,
+        seeds = [
+            b"bet".as_ref(),
+            user.key().as_ref(),
+            market.key().as_ref()
+        ],
+        bump
+    )]
+    pub user_bet: Account<'info, UserBetAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, MarketVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBetToken<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserBetAccount::LEN,
+        seeds = [
+            b"bet".as_ref(),
+            user.key().as_ref(),
+            market.key().as_ref()
+        ],
+        bump
+    )]
+    pub user_bet: Account<'info, UserBetAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = market.mint,
+        token::authority = user
+    )]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"market_token_vault".as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBet<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        close = user,
+        has_one = user @ CandleError::Unauthorized,
+        has_one = market @ CandleError::WrongMarket
+    )]
+    pub user_bet: Account<'info, UserBetAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, MarketVault>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVaultAccount>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBetToken<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        close = user,
+        has_one = user @ CandleError::Unauthorized,
+        has_one = market @ CandleError::WrongMarket
+    )]
+    pub user_bet: Account<'info, UserBetAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = market.mint,
+        token::authority = user
+    )]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"market_token_vault".as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault_authority.bump
+    )]
+    pub vault_authority: Account<'info, MarketVault>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref(), market.mint.as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleMarket<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = settler @ CandleError::Unauthorized,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub settler: Signer<'info>,
+
+    /// CHECK: single-key settlement only while no settler set exists
+    #[account(
+        seeds = [b"settlers".as_ref()],
+        bump,
+        constraint = settler_set.data_is_empty() @ CandleError::Unauthorized
+    )]
+    pub settler_set: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSettlers<'info> {
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = SettlerSet::LEN,
+        seeds = [b"settlers".as_ref()],
+        bump
+    )]
+    pub settler_set: Account<'info, SettlerSet>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitClosePrice<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = settler,
+        space = SettlementVotes::LEN,
+        seeds = [b"votes".as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub votes: Account<'info, SettlementVotes>,
+
+    #[account(
+        seeds = [b"settlers".as_ref()],
+        bump = settler_set.bump,
+        constraint = settler_set.contains(&settler.key()) @ CandleError::Unauthorized
+    )]
+    pub settler_set: Account<'info, SettlerSet>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub settler: Signer<'info>,
+
+    /// CHECK: gets the votes rent back once the market settles; must be
+    /// the settler who opened the votes account, or this one if none has
+    #[account(
+        mut,
+        constraint = votes.rent_payer().unwrap_or(settler.key()) == rent_payer.key()
+            @ CandleError::Unauthorized
+    )]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleMarketWithPyth<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        seeds = [b"asset".as_ref(), market.asset.as_bytes()],
+        bump = asset_config.bump
+    )]
+    pub asset_config: Account<'info, AssetConfig>,
+
+    /// CHECK: pinned to the asset's feed; parsed by `pyth::load_price`
+    #[account(address = asset_config.price_feed @ CandleError::WrongPriceFeed)]
+    pub price_feed: UncheckedAccount<'info>,
+
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VoidMarket<'info> {
+    #[account(mut)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = config.is_admin_or_resolver(&authority.key()) @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResettleMarket<'info> {
+    #[account(mut)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = config.is_admin_or_resolver(&authority.key()) @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        has_one = user @ CandleError::Unauthorized,
+        has_one = market @ CandleError::WrongMarket
+    )]
+    pub user_bet: Account<'info, UserBetAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, MarketVault>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVaultAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVaultAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewardToken<'info> {
+    #[account(mut, constraint = !market.paused @ CandleError::Paused)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        constraint = !config.paused @ CandleError::Paused
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(
+        mut,
+        has_one = user @ CandleError::Unauthorized,
+        has_one = market @ CandleError::WrongMarket
+    )]
+    pub user_bet: Account<'info, UserBetAccount>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = market.mint,
+        token::authority = user
+    )]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"market_token_vault".as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault_authority.bump
+    )]
+    pub vault_authority: Account<'info, MarketVault>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref(), market.mint.as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBet<'info> {
+    #[account(
+        mut,
+        close = user,
+        has_one = user @ CandleError::Unauthorized,
+        has_one = market @ CandleError::WrongMarket
+    )]
+    pub user_bet: Account<'info, UserBetAccount>,
+
+    /// CHECK: may already have been closed by close_market; the key is
+    /// pinned by `has_one` on user_bet and the data is only read if present
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMarket<'info> {
+    #[account(mut, close = admin)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, MarketVault>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVaultAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// SPL markets only: the market's token vault and the mint's fee vault
+    #[account(
+        mut,
+        seeds = [b"market_token_vault".as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub token_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref(), market.mint.as_ref()],
+        bump
+    )]
+    pub fee_token_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVaultAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    /// CHECK: any system account chosen by the admin; only credited
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFeesToken<'info> {
+    #[account(
+        mut,
+        seeds = [b"fee_vault".as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = mint)]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    /// CHECK: must be the recipient fixed in the proposal; only credited
+    #[account(mut, address = treasury.pending_recipient @ CandleError::Unauthorized)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarket<'info> {
+    /// CHECK: older layout, so it is checked by hand: owned by this
+    /// program and deserialized as a market after the upgrade
+    #[account(mut, owner = crate::ID @ CandleError::WrongMarket)]
+    pub market: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: older layout; owned by this program and matched by its
+    /// discriminator in migration::layout
+    #[account(mut, owner = crate::ID @ CandleError::UnknownAccount)]
+    pub account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarketEscrow<'info> {
+    pub market: Account<'info, MarketAccount>,
+
+    /// Only markets from before per-market vaults have none yet
+    #[account(
+        init,
+        payer = admin,
+        space = MarketVault::LEN,
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, MarketVault>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarketTokenEscrow<'info> {
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"market_token_vault".as_ref(), market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_authority
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Where every market of this mint escrowed its stakes before
+    /// per-market vaults
+    #[account(
+        mut,
+        seeds = [b"token_vault".as_ref(), mint.key().as_ref()],
+        bump,
+        token::authority = treasury
+    )]
+    pub legacy_vault: Account<'info, TokenAccount>,
+
+    #[account(constraint = market.mint == mint.key() @ CandleError::WrongMint)]
+    pub market: Account<'info, MarketAccount>,
+
+    #[account(
+        seeds = [b"vault".as_ref(), market.key().as_ref()],
+        bump = vault_authority.bump
+    )]
+    pub vault_authority: Account<'info, MarketVault>,
+
+    #[account(
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, TreasuryAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseLegacyEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
     )]
     pub treasury: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump = config.bump,
+        has_one = admin @ CandleError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub admin: Signer<'info>,
 }
 
 // -------------------------------------------------------------
@@ -318,8 +2186,64 @@ pub enum CandleError {
     Unauthorized,
     #[msg("Market has not ended yet")]
     MarketNotEnded,
-    #[msg("Insufficient funds in treasury for payout")]
+    #[msg("Insufficient funds in market vault for payout")]
     InsufficientFunds,
     #[msg("Bet exceeds the maximum allowed size")]
     InvalidBetSize,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Market has been voided")]
+    MarketVoided,
+    #[msg("Existing position is on the other side")]
+    SideMismatch,
+    #[msg("Instruction does not match the market's currency")]
+    WrongMint,
+    #[msg("Bet must be claimed before it can be closed")]
+    NotClaimed,
+    #[msg("Market still has open bets or is inside its grace period")]
+    MarketInUse,
+    #[msg("Price account is not a valid Pyth price")]
+    InvalidPriceAccount,
+    #[msg("Price feed does not match the asset's feed")]
+    WrongPriceFeed,
+    #[msg("Oracle price was not published just after the market end time")]
+    StalePrice,
+    #[msg("Oracle confidence interval is too wide")]
+    PriceTooUncertain,
+    #[msg("Invalid market parameters")]
+    InvalidMarketParams,
+    #[msg("Market or program is paused")]
+    Paused,
+    #[msg("Fee exceeds the maximum allowed")]
+    InvalidFee,
+    #[msg("No treasury withdrawal has been proposed")]
+    NoPendingWithdrawal,
+    #[msg("Treasury withdrawal is still timelocked")]
+    WithdrawalTimelocked,
+    #[msg("Expected [market, user_bet, vault] triples in remaining accounts")]
+    InvalidClaimAccounts,
+    #[msg("Settlement is final and can no longer be changed")]
+    SettlementFinal,
+    #[msg("Dispute period is out of range")]
+    InvalidDisputePeriod,
+    #[msg("Settler set is empty, too large, or the threshold is out of range")]
+    InvalidSettlerSet,
+    #[msg("Market already uses the current account layout")]
+    AlreadyMigrated,
+    #[msg("Not an account migrate_account can upgrade")]
+    UnknownAccount,
+    #[msg("Invalid asset symbol or price decimals")]
+    InvalidAsset,
+    #[msg("Asset is not enabled for new markets")]
+    AssetDisabled,
+    #[msg("Side does not exist in this market")]
+    InvalidSide,
+    #[msg("Candle high/low missing or below one another")]
+    InvalidPriceRange,
+    #[msg("Head-to-head market needs the rival asset's close price")]
+    RivalPriceMissing,
+    #[msg("Market totals do not match its pools or bet limits")]
+    InvalidMarketTotals,
+    #[msg("Expected a market from before per-market vaults and its vault address")]
+    InvalidLegacyMarkets,
 }
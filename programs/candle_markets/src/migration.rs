@@ -0,0 +1,279 @@
+use std::ops::Range;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+use anchor_lang::Discriminator;
+
+use crate::state::*;
+use crate::CandleError;
+
+// ====================================
+// ACCOUNT MIGRATION
+// ====================================
+//
+// A version 0 account is the version 1 layout minus the version byte, and
+// is recognised by its frozen size below. Upgrading it shifts every field
+// right by one byte to make room for the version.
+//
+// Every later version only appends fields or adds enum variants, which
+// existing bytes still decode as, so an account larger than its version 0
+// size already carries a version and upgrading just grows it. The one
+// exception is version 8, which drops the unused price feed from the
+// config and so shrinks it below its version 0 size.
+
+pub const MARKET_V0_LEN: usize = 362;
+
+/// Where ConfigAccount kept its price feed before version 8, version byte
+/// included.
+const CONFIG_PRICE_FEED: Range<usize> = 74..106;
+
+/// Version 0 size and current size of the account type with this
+/// discriminator. Markets are left out: they go through migrate_market,
+/// which also backfills totals.
+pub fn layout(discriminator: &[u8]) -> Option<(usize, usize)> {
+    [
+        (UserBetAccount::DISCRIMINATOR, 98, UserBetAccount::LEN),
+        (MarketVault::DISCRIMINATOR, 9, MarketVault::LEN),
+        (TreasuryAccount::DISCRIMINATOR, 57, TreasuryAccount::LEN),
+        (ConfigAccount::DISCRIMINATOR, 148, ConfigAccount::LEN),
+        (FeeVaultAccount::DISCRIMINATOR, 9, FeeVaultAccount::LEN),
+        (SettlerSet::DISCRIMINATOR, 272, SettlerSet::LEN),
+        (SettlementVotes::DISCRIMINATOR, 365, SettlementVotes::LEN),
+    ]
+    .into_iter()
+    .find(|(d, _, _)| *d == discriminator)
+    .map(|(_, v0_len, len)| (v0_len, len))
+}
+
+/// Resizes an account of an older layout to `len` and writes the current
+/// version; `payer` covers any extra rent. Returns the version it had.
+pub fn upgrade_in_place<'info>(
+    account: &AccountInfo<'info>,
+    v0_len: usize,
+    len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u8> {
+    require!(account.data_len() > 8, CandleError::AlreadyMigrated);
+
+    let mut data = account.try_borrow_data()?.to_vec();
+    let from_version = upgrade_layout(&mut data, v0_len, len)?;
+
+    let rent = Rent::get()?.minimum_balance(len);
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        let ix = system_instruction::transfer(payer.key, account.key, shortfall);
+        invoke(&ix, &[payer.clone(), account.clone(), system_program.clone()])?;
+    }
+
+    account.resize(len)?;
+    account.try_borrow_mut_data()?.copy_from_slice(&data);
+
+    Ok(from_version)
+}
+
+/// Fills in what a version 0 market may predate. The first markets only
+/// had prices, weighted pools and a settled flag: they get their raw
+/// totals, open bets and bet limits from `totals`, the original weight
+/// schedule, and, if settled, the outcome their prices decide with claims
+/// open right away. Fields a market already has are kept.
+pub fn backfill_market(market: &mut MarketAccount, totals: &MarketTotals) -> Result<()> {
+    // Weighted stakes never exceed raw ones, weights being at most 100%
+    let pools = [
+        (market.green_pool_weighted, totals.green_total),
+        (market.red_pool_weighted, totals.red_total),
+    ];
+    let vl = market.virtual_liquidity;
+    let accounted = |(pool, total): &(u64, u64)| *pool >= vl && pool - vl <= *total;
+    require!(
+        pools.iter().all(accounted),
+        CandleError::InvalidMarketTotals
+    );
+
+    market.green_total = totals.green_total;
+    market.red_total = totals.red_total;
+    market.green_bettors = totals.green_bettors;
+    market.red_bettors = totals.red_bettors;
+    market.total_claimed = totals.total_claimed;
+    // Counting a closed bet as open only delays close_market
+    market.open_bets = market.open_bets.max(totals.open_bets);
+
+    if market.max_bet == 0 {
+        require!(
+            totals.min_bet > 0 && totals.min_bet <= totals.max_bet,
+            CandleError::InvalidMarketTotals
+        );
+        market.min_bet = totals.min_bet;
+        market.max_bet = totals.max_bet;
+    }
+
+    if market.weight_schedule.is_empty() && market.final_weight == 0 {
+        let params = MarketParams::default();
+        market.weight_schedule = params.weight_schedule;
+        market.final_weight = params.final_weight;
+    }
+
+    if market.is_native() && market.decimals == 0 {
+        market.decimals = NATIVE_SOL_DECIMALS;
+    }
+
+    if market.settled && market.outcome == MarketOutcome::Pending {
+        market.outcome = market.resolve_outcome();
+        market.finalizes_at = market.end_time;
+    }
+
+    Ok(())
+}
+
+/// Brings account data to ACCOUNT_VERSION and `len` bytes and returns the
+/// version it had. Version 0 data gets a version byte inserted in front of
+/// its fields; fields the old layout never had read as 0.
+pub fn upgrade_layout(data: &mut Vec<u8>, v0_len: usize, len: usize) -> Result<u8> {
+    // A current config is shorter than a version 0 one
+    let from_version = if data.len() <= v0_len && data.len() != len {
+        data.insert(8, 0);
+        0
+    } else {
+        data[8]
+    };
+    require!(from_version < ACCOUNT_VERSION, CandleError::AlreadyMigrated);
+
+    if from_version < 8 && data[..8] == *ConfigAccount::DISCRIMINATOR {
+        data.drain(CONFIG_PRICE_FEED);
+    }
+
+    data.resize(len, 0);
+    data[8] = ACCOUNT_VERSION;
+    Ok(from_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_bet_bytes() -> Vec<u8> {
+        let bet = UserBetAccount {
+            version: ACCOUNT_VERSION,
+            user: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            side: BetSide::Green,
+            amount: 1_000,
+            weight: 70,
+            effective_stake: 700,
+            claimed: false,
+        };
+        let mut data = Vec::new();
+        bet.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn upgrades_version_0_layout() {
+        let mut current = user_bet_bytes();
+        let (v0_len, len) = layout(&current[..8]).unwrap();
+        assert_eq!(len, UserBetAccount::LEN);
+        assert_eq!(layout(MarketAccount::DISCRIMINATOR), None);
+
+        // Same bytes without the version
+        let mut data = current.clone();
+        data.remove(8);
+        assert_eq!(data.len(), v0_len);
+
+        current.resize(len, 0);
+        assert_eq!(upgrade_layout(&mut data, v0_len, len).unwrap(), 0);
+        assert_eq!(data, current);
+        assert!(upgrade_layout(&mut data, v0_len, len).is_err());
+    }
+
+    #[test]
+    fn upgrades_versioned_layout_by_appending() {
+        let mut current = vec![0u8; MarketAccount::LEN - 2];
+        current[8] = 1;
+
+        let from_version = upgrade_layout(&mut current, MARKET_V0_LEN, MarketAccount::LEN);
+        assert_eq!(from_version.unwrap(), 1);
+        assert_eq!(current.len(), MarketAccount::LEN);
+        assert_eq!(current[8], ACCOUNT_VERSION);
+    }
+
+    #[test]
+    fn drops_the_config_price_feed() {
+        let config = ConfigAccount {
+            version: ACCOUNT_VERSION,
+            admin: Pubkey::new_unique(),
+            settler: Pubkey::new_unique(),
+            bump: 254,
+            paused: true,
+            fee_bps: 100,
+            dispute_period: 3_600,
+            resolver: Pubkey::new_unique(),
+            exit_penalty_bps: 200,
+        };
+        let mut current = Vec::new();
+        config.try_serialize(&mut current).unwrap();
+        let (v0_len, len) = layout(&current[..8]).unwrap();
+
+        // Version 7: the same fields with a feed after the bump
+        let mut data = current.clone();
+        data.splice(74..74, [9u8; 32]);
+        data[8] = 7;
+
+        assert_eq!(upgrade_layout(&mut data, v0_len, len).unwrap(), 7);
+        assert_eq!(data, current);
+        assert!(upgrade_layout(&mut data, v0_len, len).is_err());
+    }
+
+    #[test]
+    fn settled_baseline_market_pays_out_after_migration() {
+        // The first MarketAccount layout: 117 bytes with a 32-byte asset
+        let mut data = MarketAccount::DISCRIMINATOR.to_vec();
+        "BTC/USDT".to_string().serialize(&mut data).unwrap();
+        for field in [7u64, 0, 14_400, 13_800, 10_000, 10_500, 800, 600, 100] {
+            field.serialize(&mut data).unwrap();
+        }
+        data.push(1);
+        data.resize(117, 0);
+
+        let from_version = upgrade_layout(&mut data, MARKET_V0_LEN, MarketAccount::LEN);
+        assert_eq!(from_version.unwrap(), 0);
+        let mut market = MarketAccount::try_deserialize(&mut &data[..]).unwrap();
+        assert!(market.outcome == MarketOutcome::Pending);
+
+        let totals = MarketTotals {
+            green_total: 1_000,
+            red_total: 500,
+            green_bettors: 1,
+            red_bettors: 1,
+            total_claimed: 0,
+            open_bets: 2,
+            min_bet: 1,
+            max_bet: 50_000_000,
+        };
+        backfill_market(&mut market, &totals).unwrap();
+        assert!(market.outcome == MarketOutcome::Green);
+        assert!(market.is_final(14_400));
+        assert_eq!(market.open_bets, 2);
+
+        let mut bet = UserBetAccount {
+            version: ACCOUNT_VERSION,
+            user: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            side: BetSide::Green,
+            amount: 1_000,
+            weight: 70,
+            effective_stake: 700,
+            claimed: false,
+        };
+        let claimed = market.claim(&mut bet, 14_400).unwrap();
+        assert!(claimed.payout > 1_000);
+        assert_eq!(market.total_claimed, claimed.payout);
+
+        // Pools the totals cannot account for are refused
+        let mut market = MarketAccount::try_deserialize(&mut &data[..]).unwrap();
+        let totals = MarketTotals {
+            green_total: 600,
+            ..totals
+        };
+        assert!(backfill_market(&mut market, &totals).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+
+use crate::CandleError;
+
+// ====================================
+// PYTH PRICE ACCOUNT (legacy v2 layout)
+// ====================================
+//
+// Only the handful of fields settlement needs are read, straight from the
+// account bytes, so the program does not depend on the Pyth SDK.
+
+pub const MAGIC: u32 = 0xa1b2_c3d4;
+pub const VERSION_2: u32 = 2;
+pub const ACCOUNT_TYPE_PRICE: u32 = 3;
+pub const STATUS_TRADING: u32 = 1;
+
+const OFFSET_MAGIC: usize = 0;
+const OFFSET_VERSION: usize = 4;
+const OFFSET_ACCOUNT_TYPE: usize = 8;
+const OFFSET_EXPO: usize = 20;
+const OFFSET_TIMESTAMP: usize = 96;
+const OFFSET_AGG_PRICE: usize = 208;
+const OFFSET_AGG_CONF: usize = 216;
+const OFFSET_AGG_STATUS: usize = 224;
+
+/// Size of the header plus the aggregate price; components follow but are unused.
+pub const MIN_PRICE_ACCOUNT_LEN: usize = 240;
+
+/// Max delay (seconds) between `end_time` and the price publish time.
+pub const MAX_PUBLISH_DELAY: i64 = 60;
+
+/// Max confidence interval, in basis points of the price.
+pub const MAX_CONF_BPS: u64 = 100;
+
+pub struct PythPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl PythPrice {
+    /// True when the price was published at or shortly after `end_time`.
+    /// An earlier price would let the caller settle on one they picked
+    /// before the candle closed.
+    pub fn published_at_close(&self, end_time: i64) -> bool {
+        self.publish_time >= end_time && self.publish_time - end_time <= MAX_PUBLISH_DELAY
+    }
+
+    /// True when `conf` is within `max_bps` of the price.
+    pub fn confidence_ok(&self, max_bps: u64) -> bool {
+        let price = self.price.unsigned_abs() as u128;
+        (self.conf as u128) * 10_000 <= price * max_bps as u128
+    }
+
+    /// Converts the price to a fixed-point integer with `decimals` decimals.
+    pub fn scaled_to(&self, decimals: i32) -> Option<u64> {
+        if self.price <= 0 {
+            return None;
+        }
+
+        let price = self.price as u64;
+        let shift = self.expo.checked_add(decimals)?;
+
+        if shift >= 0 {
+            price.checked_mul(10u64.checked_pow(shift as u32)?)
+        } else {
+            Some(price / 10u64.checked_pow(shift.unsigned_abs())?)
+        }
+    }
+}
+
+/// Parses the aggregate price out of a Pyth price account's data.
+pub fn load_price(data: &[u8]) -> Result<PythPrice> {
+    require!(data.len() >= MIN_PRICE_ACCOUNT_LEN, CandleError::InvalidPriceAccount);
+    require!(read_u32(data, OFFSET_MAGIC) == MAGIC, CandleError::InvalidPriceAccount);
+    require!(read_u32(data, OFFSET_VERSION) == VERSION_2, CandleError::InvalidPriceAccount);
+    require!(
+        read_u32(data, OFFSET_ACCOUNT_TYPE) == ACCOUNT_TYPE_PRICE,
+        CandleError::InvalidPriceAccount
+    );
+    require!(
+        read_u32(data, OFFSET_AGG_STATUS) == STATUS_TRADING,
+        CandleError::InvalidPriceAccount
+    );
+
+    Ok(PythPrice {
+        price: read_u64(data, OFFSET_AGG_PRICE) as i64,
+        conf: read_u64(data, OFFSET_AGG_CONF),
+        expo: read_u32(data, OFFSET_EXPO) as i32,
+        publish_time: read_u64(data, OFFSET_TIMESTAMP) as i64,
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price_account(price: i64, conf: u64, expo: i32, publish_time: i64) -> Vec<u8> {
+        let mut data = vec![0u8; MIN_PRICE_ACCOUNT_LEN];
+        data[OFFSET_MAGIC..OFFSET_MAGIC + 4].copy_from_slice(&MAGIC.to_le_bytes());
+        data[OFFSET_VERSION..OFFSET_VERSION + 4].copy_from_slice(&VERSION_2.to_le_bytes());
+        data[OFFSET_ACCOUNT_TYPE..OFFSET_ACCOUNT_TYPE + 4]
+            .copy_from_slice(&ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[OFFSET_EXPO..OFFSET_EXPO + 4].copy_from_slice(&expo.to_le_bytes());
+        data[OFFSET_TIMESTAMP..OFFSET_TIMESTAMP + 8].copy_from_slice(&publish_time.to_le_bytes());
+        data[OFFSET_AGG_PRICE..OFFSET_AGG_PRICE + 8].copy_from_slice(&price.to_le_bytes());
+        data[OFFSET_AGG_CONF..OFFSET_AGG_CONF + 8].copy_from_slice(&conf.to_le_bytes());
+        data[OFFSET_AGG_STATUS..OFFSET_AGG_STATUS + 4]
+            .copy_from_slice(&STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn reads_and_scales_price() {
+        // 64_123.45678901 with expo -8
+        let data = price_account(6_412_345_678_901, 1_000_000_000, -8, 1_700_000_000);
+        let price = load_price(&data).unwrap();
+
+        assert_eq!(price.publish_time, 1_700_000_000);
+        assert_eq!(price.scaled_to(2), Some(6_412_345));
+        assert!(price.confidence_ok(MAX_CONF_BPS));
+    }
+
+    #[test]
+    fn rejects_prices_published_before_the_close() {
+        let end_time = 1_700_000_000;
+        let at = |publish_time| {
+            load_price(&price_account(100_000, 0, -2, publish_time))
+                .unwrap()
+                .published_at_close(end_time)
+        };
+
+        assert!(at(end_time));
+        assert!(at(end_time + MAX_PUBLISH_DELAY));
+        assert!(!at(end_time - 1));
+        assert!(!at(end_time + MAX_PUBLISH_DELAY + 1));
+    }
+
+    #[test]
+    fn rejects_wide_confidence() {
+        // conf of 2% of the price
+        let data = price_account(100_000, 2_000, -2, 0);
+        let price = load_price(&data).unwrap();
+
+        assert!(!price.confidence_ok(MAX_CONF_BPS));
+    }
+
+    #[test]
+    fn rejects_non_price_accounts() {
+        let mut data = price_account(100_000, 0, -2, 0);
+        data[OFFSET_MAGIC] = 0;
+
+        assert!(load_price(&data).is_err());
+        assert!(load_price(&data[..100]).is_err());
+    }
+}
@@ -1,11 +1,62 @@
 use anchor_lang::prelude::*;
 
+use crate::CandleError;
+
+/// Layout version stored right after the discriminator of every account.
+/// Accounts created before it existed are version 0; older accounts are
+/// upgraded by the migrate_* instructions.
+///
+/// 1: version byte. 2: exit penalty on config and markets. 3: range
+/// markets (price buckets). 4: strike price on markets. 5: volatility
+/// markets, candle high/low on markets and settlement votes. 6: head-to-head
+/// markets, rival close price votes. 7: stakes still owed from the treasury.
+/// 8: unused price feed dropped from config.
+pub const ACCOUNT_VERSION: u8 = 8;
+
+/// Longest `asset` symbol a market can store.
+pub const MAX_ASSET_LEN: usize = 32;
+
+/// Decimals recorded for native SOL markets (lamports).
+pub const NATIVE_SOL_DECIMALS: u8 = 9;
+
+/// Default decimals of market prices (i.e. cents) for a new asset.
+pub const PRICE_DECIMALS: u8 = 2;
+
+/// Upper bound on an asset's price decimals, so prices still fit a u64.
+pub const MAX_PRICE_DECIMALS: u8 = 8;
+
+/// Seconds after `end_time` before a market with no open bets can be closed.
+pub const MARKET_CLOSE_GRACE: i64 = 3 * 24 * 3600;
+
+/// Max number of weight tiers a market can store.
+pub const MAX_WEIGHT_TIERS: usize = 8;
+
+/// Max number of price buckets in a range market.
+pub const MAX_BUCKETS: usize = 8;
+
+/// Upper bound on the protocol fee (10% of winnings).
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Upper bound on the penalty for leaving a market early (10% of the stake).
+pub const MAX_EXIT_PENALTY_BPS: u16 = 1_000;
+
+/// Seconds between proposing and executing a treasury withdrawal.
+pub const WITHDRAWAL_DELAY: i64 = 2 * 24 * 3600;
+
+/// Max number of keys in the settler set.
+pub const MAX_SETTLERS: usize = 8;
+
+/// Longest dispute window the admin can configure; kept well inside
+/// MARKET_CLOSE_GRACE so a market is always final before it can be closed.
+pub const MAX_DISPUTE_PERIOD: i64 = 24 * 3600;
+
 // ====================================
 // MARKET ACCOUNT
 // ====================================
 
 #[account]
 pub struct MarketAccount {
+    pub version: u8,
     pub asset: String,
     pub market_id: u64,
     pub start_time: i64,
@@ -17,17 +68,617 @@ pub struct MarketAccount {
     pub red_pool_weighted: u64,
     pub virtual_liquidity: u64,
     pub settled: bool,
+    pub outcome: MarketOutcome,
+    /// SPL mint the market is denominated in; default pubkey = native SOL
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// UserBetAccounts that still exist for this market
+    pub open_bets: u32,
+    pub weight_schedule: Vec<WeightTier>,
+    pub final_weight: u64,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    /// Admin emergency stop for this market only
+    pub paused: bool,
+    /// Protocol fee on winnings, fixed when the market is created
+    pub fee_bps: u16,
+    /// Fees and exit penalties moved to the fee vault so far
+    pub fees_accrued: u64,
+    /// End of the dispute window: settlement is final and claims unlock
+    pub finalizes_at: i64,
+    /// Raw stakes per side, without weights or virtual liquidity
+    pub green_total: u64,
+    pub red_total: u64,
+    /// Positions opened on each side
+    pub green_bettors: u32,
+    pub red_bettors: u32,
+    /// Paid out to bettors by claims so far, net of fees
+    pub total_claimed: u64,
+    /// Kept from the stake of a bet withdrawn before lock, fixed at creation
+    pub exit_penalty_bps: u16,
+    /// Range markets only: where buckets 1.. start, as a change from the
+    /// strike price in basis points. Empty for Green/Red markets
+    pub bucket_bounds: Vec<i32>,
+    /// Range markets only: weighted pool, raw total and bettors per bucket
+    pub bucket_pools_weighted: Vec<u64>,
+    pub bucket_totals: Vec<u64>,
+    pub bucket_bettors: Vec<u32>,
+    /// Price the close is compared with; 0 means the open price, which is
+    /// also what markets from before strikes read as
+    pub strike_price: u64,
+    /// Volatility markets only: Green wins if the candle's high - low
+    /// exceeds this many bps of the open, Red otherwise. 0 otherwise
+    pub volatility_threshold_bps: u32,
+    /// Candle high and low recorded at settlement
+    pub high_price: u64,
+    pub low_price: u64,
+    /// Head-to-head markets only: the asset `asset` is measured against.
+    /// Green wins if `asset` has the better return over the candle, Red if
+    /// the rival does. Empty otherwise
+    pub rival_asset: String,
+    pub rival_open_price: u64,
+    pub rival_close_price: u64,
 }
 
 impl MarketAccount {
-    pub const LEN: usize = 8
-        + 4 + 32
+    pub const LEN: usize = 8 + 1
+        + 4 + MAX_ASSET_LEN
         + 8
         + 8 + 8 + 8
         + 8 + 8
         + 8 + 8
         + 8
-        + 1;
+        + 1
+        + 2
+        + 32 + 1
+        + 4
+        + 4 + MAX_WEIGHT_TIERS * WeightTier::LEN
+        + 8
+        + 8 + 8
+        + 1
+        + 2 + 8
+        + 8
+        + 8 + 8
+        + 4 + 4
+        + 8
+        + 2
+        + 4 + (MAX_BUCKETS - 1) * 4
+        + 4 + MAX_BUCKETS * 8
+        + 4 + MAX_BUCKETS * 8
+        + 4 + MAX_BUCKETS * 4
+        + 8
+        + 4
+        + 8 + 8
+        + 4 + MAX_ASSET_LEN
+        + 8 + 8;
+
+    pub fn is_native(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    /// Price the outcome is decided against.
+    pub fn strike(&self) -> u64 {
+        if self.strike_price == 0 {
+            self.open_price
+        } else {
+            self.strike_price
+        }
+    }
+
+    /// Decided on the candle's high - low rather than its close.
+    pub fn is_volatility(&self) -> bool {
+        self.volatility_threshold_bps > 0
+    }
+
+    /// Decided on the return of `asset` against `rival_asset`.
+    pub fn is_head_to_head(&self) -> bool {
+        !self.rival_asset.is_empty()
+    }
+
+    /// Bets on price buckets rather than Green/Red.
+    pub fn is_range(&self) -> bool {
+        !self.bucket_bounds.is_empty()
+    }
+
+    /// Whether `side` can be bet on in this market.
+    pub fn has_side(&self, side: &BetSide) -> bool {
+        match side {
+            BetSide::Bucket(i) => (*i as usize) < self.bucket_pools_weighted.len(),
+            BetSide::Green | BetSide::Red => !self.is_range(),
+        }
+    }
+
+    /// Weighted pool of every side, indexed by BetSide::index.
+    pub fn pools_weighted(&self) -> Vec<u64> {
+        if self.is_range() {
+            self.bucket_pools_weighted.clone()
+        } else {
+            vec![self.green_pool_weighted, self.red_pool_weighted]
+        }
+    }
+
+    /// Adds `amount` to the bettor's position and the weighted pools and
+    /// returns the bet's effective stake.
+    /// Moving the funds is left to the calling instruction.
+    pub fn record_bet(
+        &mut self,
+        user_bet: &mut UserBetAccount,
+        user: Pubkey,
+        market: Pubkey,
+        side: BetSide,
+        amount: u64,
+        now: i64,
+    ) -> Result<u64> {
+        require!(self.outcome != MarketOutcome::Void, CandleError::MarketVoided);
+        require!(now < self.lock_time, CandleError::MarketLocked);
+        require!(!user_bet.claimed, CandleError::Unauthorized);
+        require!(amount > 0, CandleError::InvalidBetSize);
+        require!(self.has_side(&side), CandleError::InvalidSide);
+
+        // Adding to an existing position is allowed, but only on the same side
+        let is_new_position = user_bet.amount == 0;
+        if !is_new_position {
+            require!(user_bet.side == side, CandleError::SideMismatch);
+        }
+
+        // The limits apply to the whole position, not each top-up
+        let total_amount = user_bet
+            .amount
+            .checked_add(amount)
+            .ok_or(CandleError::MathOverflow)?;
+        require!(
+            total_amount >= self.min_bet && total_amount <= self.max_bet,
+            CandleError::InvalidBetSize
+        );
+
+        let weight = self.weight_at(now);
+
+        let effective_stake = amount
+            .checked_mul(weight)
+            .ok_or(CandleError::MathOverflow)?
+            / 100;
+
+        let (pool_weighted, total, bettors) = self.side_mut(&side);
+
+        *pool_weighted = pool_weighted
+            .checked_add(effective_stake)
+            .ok_or(CandleError::MathOverflow)?;
+        *total = total.checked_add(amount).ok_or(CandleError::MathOverflow)?;
+
+        if is_new_position {
+            *bettors = bettors.checked_add(1).ok_or(CandleError::MathOverflow)?;
+            self.open_bets = self.open_bets.checked_add(1).ok_or(CandleError::MathOverflow)?;
+        }
+
+        let total_effective = user_bet
+            .effective_stake
+            .checked_add(effective_stake)
+            .ok_or(CandleError::MathOverflow)?;
+
+        user_bet.version = ACCOUNT_VERSION;
+        user_bet.user = user;
+        user_bet.market = market;
+        user_bet.side = side;
+        user_bet.amount = total_amount;
+        user_bet.effective_stake = total_effective;
+        // Blended weight across every tier this position was built in
+        user_bet.weight = total_effective
+            .checked_mul(100)
+            .ok_or(CandleError::MathOverflow)?
+            / total_amount;
+        user_bet.claimed = false;
+
+        Ok(effective_stake)
+    }
+
+    /// Takes a whole position back out of the pools before betting locks.
+    /// The stake is refunded minus the market's exit penalty; moving the
+    /// funds and closing the bet is left to the calling instruction.
+    pub fn withdraw(&mut self, user_bet: &UserBetAccount, now: i64) -> Result<ExitAmounts> {
+        require!(self.outcome != MarketOutcome::Void, CandleError::MarketVoided);
+        require!(now < self.lock_time, CandleError::MarketLocked);
+        require!(!user_bet.claimed && user_bet.amount > 0, CandleError::Unauthorized);
+
+        let (pool_weighted, total, bettors) = self.side_mut(&user_bet.side);
+
+        *pool_weighted = pool_weighted
+            .checked_sub(user_bet.effective_stake)
+            .ok_or(CandleError::MathOverflow)?;
+        // Totals of migrated markets were backfilled off-chain, so never fail on them
+        *total = total.saturating_sub(user_bet.amount);
+        *bettors = bettors.saturating_sub(1);
+        self.open_bets = self.open_bets.saturating_sub(1);
+
+        // Same bps math as the claim fee
+        let penalty = protocol_fee(user_bet.amount, self.exit_penalty_bps);
+        self.fees_accrued = self
+            .fees_accrued
+            .checked_add(penalty)
+            .ok_or(CandleError::MathOverflow)?;
+
+        Ok(ExitAmounts { refund: user_bet.amount - penalty, penalty })
+    }
+
+    /// Weighted pool, raw total and bettor count of one side.
+    fn side_mut(&mut self, side: &BetSide) -> (&mut u64, &mut u64, &mut u32) {
+        match side {
+            BetSide::Green => (
+                &mut self.green_pool_weighted,
+                &mut self.green_total,
+                &mut self.green_bettors,
+            ),
+            BetSide::Red => (
+                &mut self.red_pool_weighted,
+                &mut self.red_total,
+                &mut self.red_bettors,
+            ),
+            BetSide::Bucket(i) => (
+                &mut self.bucket_pools_weighted[*i as usize],
+                &mut self.bucket_totals[*i as usize],
+                &mut self.bucket_bettors[*i as usize],
+            ),
+        }
+    }
+
+    /// Weight (percent) for a bet placed at `now`.
+    pub fn weight_at(&self, now: i64) -> u64 {
+        let elapsed = now - self.start_time;
+
+        self.weight_schedule
+            .iter()
+            .find(|tier| elapsed < tier.until_offset)
+            .map(|tier| tier.weight)
+            .unwrap_or(self.final_weight)
+    }
+
+    /// Stakes a market from before per-market vaults still holds in the
+    /// treasury: everything staked, less what claims paid out and their
+    /// fees. Such markets never saw a withdrawal.
+    pub fn legacy_escrow(&self) -> Result<u64> {
+        let staked = self
+            .bucket_totals
+            .iter()
+            .chain([&self.green_total, &self.red_total])
+            .try_fold(0u64, |sum, total| sum.checked_add(*total))
+            .ok_or(CandleError::MathOverflow)?;
+
+        Ok(staked.saturating_sub(self.total_claimed.saturating_add(self.fees_accrued)))
+    }
+
+    /// Settled and past the dispute window.
+    pub fn is_final(&self, now: i64) -> bool {
+        self.settled && now >= self.finalizes_at
+    }
+
+    /// Marks the bet claimed and splits what it is owed between the
+    /// bettor and the fee vault.
+    pub fn claim(&mut self, user_bet: &mut UserBetAccount, now: i64) -> Result<ClaimAmounts> {
+        require!(self.is_final(now), CandleError::SettlementPending);
+        require!(!user_bet.claimed, CandleError::AlreadyClaimed);
+
+        let gross = self.payout_for(&user_bet.side, user_bet.amount, user_bet.effective_stake)?;
+        let fee = protocol_fee(gross.saturating_sub(user_bet.amount), self.fee_bps);
+
+        let payout = gross - fee;
+
+        self.fees_accrued = self.fees_accrued.checked_add(fee).ok_or(CandleError::MathOverflow)?;
+        self.total_claimed = self
+            .total_claimed
+            .checked_add(payout)
+            .ok_or(CandleError::MathOverflow)?;
+        user_bet.claimed = true;
+
+        Ok(ClaimAmounts { payout, fee })
+    }
+
+    /// Records the candle's close, high and low, and the rival's close for
+    /// a head-to-head market, and decides the outcome. Claims stay locked
+    /// for `dispute_period` seconds so a bad price can still be corrected.
+    pub fn settle(
+        &mut self,
+        close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
+        now: i64,
+        dispute_period: i64,
+    ) -> Result<()> {
+        require!(self.outcome != MarketOutcome::Void, CandleError::MarketVoided);
+        require!(now >= self.end_time, CandleError::MarketNotEnded);
+        require!(!self.settled, CandleError::Unauthorized);
+        require!(low_price <= high_price, CandleError::InvalidPriceRange);
+        // A zero low is a missing price, not a 100% range
+        require!(
+            !self.is_volatility() || low_price > 0,
+            CandleError::InvalidPriceRange
+        );
+        require!(
+            !self.is_head_to_head() || rival_close_price > 0,
+            CandleError::RivalPriceMissing
+        );
+
+        self.close_price = close_price;
+        self.high_price = high_price;
+        self.low_price = low_price;
+        self.rival_close_price = rival_close_price;
+        self.outcome = self.resolve_outcome();
+        self.settled = true;
+        self.finalizes_at = now.saturating_add(dispute_period);
+
+        Ok(())
+    }
+
+    /// Corrects the close price during the dispute window. The window is
+    /// not extended.
+    pub fn resettle(
+        &mut self,
+        close_price: u64,
+        high_price: u64,
+        low_price: u64,
+        rival_close_price: u64,
+        now: i64,
+    ) -> Result<()> {
+        require!(self.settled, CandleError::SettlementPending);
+        require!(self.outcome != MarketOutcome::Void, CandleError::MarketVoided);
+        require!(now < self.finalizes_at, CandleError::SettlementFinal);
+        require!(low_price <= high_price, CandleError::InvalidPriceRange);
+        // A zero low is a missing price, not a 100% range
+        require!(
+            !self.is_volatility() || low_price > 0,
+            CandleError::InvalidPriceRange
+        );
+        require!(
+            !self.is_head_to_head() || rival_close_price > 0,
+            CandleError::RivalPriceMissing
+        );
+
+        self.close_price = close_price;
+        self.high_price = high_price;
+        self.low_price = low_price;
+        self.rival_close_price = rival_close_price;
+        self.outcome = self.resolve_outcome();
+
+        Ok(())
+    }
+
+    /// Refunds every bet through claim_reward, right away. The admin may
+    /// void until settlement is final; the resolver only inside the
+    /// dispute window.
+    pub fn void(&mut self, by_admin: bool, now: i64) -> Result<()> {
+        require!(
+            self.outcome != MarketOutcome::Void,
+            CandleError::MarketVoided
+        );
+        require!(
+            !self.settled || now < self.finalizes_at,
+            CandleError::SettlementFinal
+        );
+        require!(by_admin || self.settled, CandleError::Unauthorized);
+
+        self.outcome = MarketOutcome::Void;
+        self.settled = true;
+        self.finalizes_at = now;
+
+        Ok(())
+    }
+
+    pub fn resolve_outcome(&self) -> MarketOutcome {
+        if self.is_head_to_head() {
+            return resolve_head_to_head_outcome(
+                self.open_price,
+                self.close_price,
+                self.rival_open_price,
+                self.rival_close_price,
+                self.green_pool_weighted,
+                self.red_pool_weighted,
+                self.virtual_liquidity,
+            );
+        }
+
+        if self.is_volatility() {
+            return resolve_volatility_outcome(
+                self.open_price,
+                self.high_price,
+                self.low_price,
+                self.volatility_threshold_bps,
+                self.green_pool_weighted,
+                self.red_pool_weighted,
+                self.virtual_liquidity,
+            );
+        }
+
+        if self.is_range() {
+            return resolve_range_outcome(
+                self.strike(),
+                self.close_price,
+                &self.bucket_bounds,
+                &self.bucket_pools_weighted,
+                self.virtual_liquidity,
+            );
+        }
+
+        resolve_outcome(
+            self.strike(),
+            self.close_price,
+            self.green_pool_weighted,
+            self.red_pool_weighted,
+            self.virtual_liquidity,
+        )
+    }
+
+    /// Lamports owed to a bet on `side` once the market is settled.
+    pub fn payout_for(&self, side: &BetSide, amount: u64, effective_stake: u64) -> Result<u64> {
+        let winning_side = match self.outcome {
+            MarketOutcome::Pending => return err!(CandleError::SettlementPending),
+            MarketOutcome::Void => return Ok(amount),
+            MarketOutcome::Green => BetSide::Green,
+            MarketOutcome::Red => BetSide::Red,
+            MarketOutcome::Bucket(i) => BetSide::Bucket(i),
+        };
+
+        if *side != winning_side {
+            return Ok(0);
+        }
+
+        let (winning_pool, losing_pool) =
+            split_pools(&self.pools_weighted(), winning_side.index(), self.virtual_liquidity);
+
+        parimutuel_payout(amount, effective_stake, winning_pool, losing_pool)
+            .ok_or_else(|| error!(CandleError::MathOverflow))
+    }
+}
+
+// ====================================
+// MARKET PARAMETERS
+// ====================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct WeightTier {
+    /// Applies to bets placed before `start_time + until_offset`
+    pub until_offset: i64,
+    /// Percent of the stake that counts toward the pool (1..=100)
+    pub weight: u64,
+}
+
+impl WeightTier {
+    pub const LEN: usize = 8 + 8;
+}
+
+/// Per-market knobs passed to create_market.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct MarketParams {
+    /// Tiers in increasing `until_offset` order
+    pub weight_schedule: Vec<WeightTier>,
+    /// Weight for bets placed after the last tier
+    pub final_weight: u64,
+    /// Betting locks this many seconds before `end_time`
+    pub lock_offset: i64,
+    pub virtual_liquidity: u64,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    /// Empty for a Green/Red market; otherwise makes a range market with
+    /// one more bucket than bounds (see MarketAccount::bucket_bounds)
+    pub bucket_bounds: Vec<i32>,
+    /// Decide the market against this price instead of the open
+    pub strike_price: Option<u64>,
+    /// Makes a volatility market (see MarketAccount::volatility_threshold_bps)
+    pub volatility_threshold_bps: Option<u32>,
+    /// Makes a head-to-head market against this asset
+    pub rival: Option<RivalAsset>,
+}
+
+/// The second asset of a head-to-head market and its price at the open.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct RivalAsset {
+    pub asset: String,
+    pub open_price: u64,
+}
+
+impl Default for MarketParams {
+    /// The original 4h market: 100/70/50/20 weights at 1h/2h/3h,
+    /// locking 10 minutes before close.
+    fn default() -> Self {
+        MarketParams {
+            weight_schedule: vec![
+                WeightTier { until_offset: 3600, weight: 100 },
+                WeightTier { until_offset: 7200, weight: 70 },
+                WeightTier { until_offset: 10800, weight: 50 },
+            ],
+            final_weight: 20,
+            lock_offset: 600,
+            virtual_liquidity: 100,
+            min_bet: 1,
+            max_bet: 50_000_000,
+            bucket_bounds: Vec::new(),
+            strike_price: None,
+            volatility_threshold_bps: None,
+            rival: None,
+        }
+    }
+}
+
+impl MarketParams {
+    pub fn validate(&self, start_time: i64, end_time: i64) -> Result<()> {
+        let duration = end_time - start_time;
+
+        require!(
+            self.weight_schedule.len() <= MAX_WEIGHT_TIERS,
+            CandleError::InvalidMarketParams
+        );
+        require!(
+            (1..=100).contains(&self.final_weight),
+            CandleError::InvalidWeight
+        );
+
+        let mut prev_offset = 0;
+        for tier in &self.weight_schedule {
+            require!((1..=100).contains(&tier.weight), CandleError::InvalidWeight);
+            require!(tier.until_offset > prev_offset, CandleError::InvalidWeight);
+            prev_offset = tier.until_offset;
+        }
+
+        require!(
+            self.lock_offset >= 0 && self.lock_offset < duration,
+            CandleError::InvalidMarketParams
+        );
+        require!(
+            self.min_bet > 0 && self.min_bet <= self.max_bet,
+            CandleError::InvalidMarketParams
+        );
+
+        // Strictly increasing, and no lower than a -100% move
+        require!(
+            self.bucket_bounds.len() < MAX_BUCKETS,
+            CandleError::InvalidMarketParams
+        );
+        let mut prev_bound = -10_000;
+        for &bound in &self.bucket_bounds {
+            require!(bound > prev_bound, CandleError::InvalidMarketParams);
+            prev_bound = bound;
+        }
+
+        require!(self.strike_price != Some(0), CandleError::InvalidMarketParams);
+
+        // A volatility market has neither buckets nor a strike
+        if let Some(threshold) = self.volatility_threshold_bps {
+            require!(
+                threshold > 0 && self.bucket_bounds.is_empty() && self.strike_price.is_none(),
+                CandleError::InvalidMarketParams
+            );
+        }
+
+        // Nor does a head-to-head market, which is also not a volatility one
+        if let Some(rival) = &self.rival {
+            require!(
+                is_valid_symbol(&rival.asset) && rival.open_price > 0,
+                CandleError::InvalidMarketParams
+            );
+            require!(
+                self.bucket_bounds.is_empty()
+                    && self.strike_price.is_none()
+                    && self.volatility_threshold_bps.is_none(),
+                CandleError::InvalidMarketParams
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Raw totals for a market created before MarketAccount tracked them,
+/// rebuilt off-chain from its bets and passed to migrate_market.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Default)]
+pub struct MarketTotals {
+    pub green_total: u64,
+    pub red_total: u64,
+    pub green_bettors: u32,
+    pub red_bettors: u32,
+    pub total_claimed: u64,
+    /// Bets whose UserBetAccount may still exist
+    pub open_bets: u32,
+    /// Limits for a market from before per-market bet limits
+    pub min_bet: u64,
+    pub max_bet: u64,
 }
 
 // ====================================
@@ -36,6 +687,7 @@ impl MarketAccount {
 
 #[account]
 pub struct UserBetAccount {
+    pub version: u8,
     pub user: Pubkey,
     pub market: Pubkey,
     pub side: BetSide,
@@ -46,24 +698,309 @@ pub struct UserBetAccount {
 }
 
 impl UserBetAccount {
-    pub const LEN: usize = 8
+    pub const LEN: usize = 8 + 1
         + 32 + 32
-        + 1
+        + 2
         + 8 + 8 + 8
         + 1;
 }
 
+// ====================================
+// MARKET VAULT (escrows one market's SOL stakes)
+// ====================================
+
+/// PDA at `[b"vault", market]`. Holds the market's SOL stakes directly and
+/// is the token authority of its `[b"market_token_vault", market]` account,
+/// so a market can only ever pay out what was staked in it.
+#[account]
+pub struct MarketVault {
+    pub version: u8,
+    pub bump: u8,
+}
+
+impl MarketVault {
+    pub const LEN: usize = 8 + 1 + 1;
+}
+
 // ====================================
 // TREASURY ACCOUNT
 // ====================================
 
 #[account]
 pub struct TreasuryAccount {
+    pub version: u8,
     pub bump: u8,
+    /// Proposed withdrawal; zero when nothing is pending
+    pub pending_amount: u64,
+    pub pending_recipient: Pubkey,
+    pub pending_executable_at: i64,
+    /// Stakes of markets from before per-market vaults still held here;
+    /// migrate_market_escrow moves them out market by market
+    pub legacy_escrow: u64,
 }
 
 impl TreasuryAccount {
-    pub const LEN: usize = 8 + 1;
+    pub const LEN: usize = 8 + 1 + 1
+        + 8 + 32 + 8
+        + 8;
+
+    /// Clears the pending withdrawal once its timelock has passed and
+    /// returns its amount. `available` is the balance above rent; stakes
+    /// still owed to legacy markets are never paid out.
+    pub fn take_withdrawal(&mut self, available: u64, now: i64) -> Result<u64> {
+        let amount = self.pending_amount;
+
+        require!(amount > 0, CandleError::NoPendingWithdrawal);
+        require!(
+            now >= self.pending_executable_at,
+            CandleError::WithdrawalTimelocked
+        );
+        require!(
+            available.saturating_sub(self.legacy_escrow) >= amount,
+            CandleError::InsufficientFunds
+        );
+
+        self.pending_amount = 0;
+        Ok(amount)
+    }
+
+    /// Lowers the legacy reserve to `still_owed`, what the legacy markets
+    /// still owe, and returns how much it dropped. It never grows back.
+    pub fn release_legacy_escrow(&mut self, still_owed: u64) -> u64 {
+        let released = self.legacy_escrow.saturating_sub(still_owed);
+        self.legacy_escrow -= released;
+        released
+    }
+}
+
+// ====================================
+// CONFIG ACCOUNT
+// ====================================
+
+#[account]
+pub struct ConfigAccount {
+    pub version: u8,
+    pub admin: Pubkey,
+    pub settler: Pubkey,
+    pub bump: u8,
+    /// Admin emergency stop for every market
+    pub paused: bool,
+    /// Protocol fee applied to markets created from now on
+    pub fee_bps: u16,
+    /// Seconds after settlement during which it can be corrected
+    pub dispute_period: i64,
+    /// May re-settle or void during the dispute window, besides the admin
+    pub resolver: Pubkey,
+    /// Exit penalty applied to markets created from now on
+    pub exit_penalty_bps: u16,
+}
+
+impl ConfigAccount {
+    pub const LEN: usize = 8 + 1
+        + 32 + 32
+        + 1
+        + 1
+        + 2
+        + 8 + 32
+        + 2;
+
+    pub fn is_admin_or_resolver(&self, key: &Pubkey) -> bool {
+        *key == self.admin || *key == self.resolver
+    }
+}
+
+// ====================================
+// ASSET CONFIG (one per tradable symbol)
+// ====================================
+
+/// PDA at `[b"asset", symbol]`. Markets can only be created for an enabled
+/// asset, and are settled on-chain from its oracle feed.
+#[account]
+pub struct AssetConfig {
+    pub version: u8,
+    pub symbol: String,
+    /// Decimals of this asset's market prices; fixed at registration
+    pub price_decimals: u8,
+    /// Pyth price account accepted by settle_market_with_pyth
+    pub price_feed: Pubkey,
+    /// Cleared to stop new markets; existing ones are unaffected
+    pub enabled: bool,
+    pub bump: u8,
+}
+
+impl AssetConfig {
+    pub const LEN: usize = 8 + 1
+        + 4 + MAX_ASSET_LEN
+        + 1
+        + 32
+        + 1 + 1;
+}
+
+/// Symbols are 1 to MAX_ASSET_LEN characters of `A-Z`, `0-9`, `/`, `-` and
+/// `_`, e.g. "BTC/USDT".
+pub fn is_valid_symbol(symbol: &str) -> bool {
+    !symbol.is_empty()
+        && symbol.len() <= MAX_ASSET_LEN
+        && symbol
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b"/-_".contains(&b))
+}
+
+// ====================================
+// FEE VAULT (protocol revenue, SOL)
+// ====================================
+
+#[account]
+pub struct FeeVaultAccount {
+    pub version: u8,
+    pub bump: u8,
+}
+
+impl FeeVaultAccount {
+    pub const LEN: usize = 8 + 1 + 1;
+}
+
+/// What a claim pays out, net of the protocol fee.
+pub struct ClaimAmounts {
+    pub payout: u64,
+    pub fee: u64,
+}
+
+/// What an early exit refunds, net of the exit penalty.
+pub struct ExitAmounts {
+    pub refund: u64,
+    pub penalty: u64,
+}
+
+// ====================================
+// SETTLER SET (M-of-N close price votes)
+// ====================================
+
+/// PDA at `[b"settlers"]`. Once it exists, markets can only be settled by
+/// `threshold` settlers submitting close prices within `tolerance_bps` of
+/// each other; the single-key settle_market is disabled.
+#[account]
+pub struct SettlerSet {
+    pub version: u8,
+    pub settlers: Vec<Pubkey>,
+    pub threshold: u8,
+    /// Max spread between agreeing prices, in basis points
+    pub tolerance_bps: u16,
+    pub bump: u8,
+}
+
+impl SettlerSet {
+    pub const LEN: usize = 8 + 1
+        + 4 + MAX_SETTLERS * 32
+        + 1
+        + 2
+        + 1;
+
+    pub fn contains(&self, key: &Pubkey) -> bool {
+        self.settlers.contains(key)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PriceVote {
+    pub settler: Pubkey,
+    pub price: u64,
+}
+
+impl PriceVote {
+    pub const LEN: usize = 32 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RangeVote {
+    pub settler: Pubkey,
+    pub high: u64,
+    pub low: u64,
+}
+
+impl RangeVote {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+/// PDA at `[b"votes", market]`: close prices, and candle highs and lows,
+/// submitted so far.
+#[account]
+pub struct SettlementVotes {
+    pub version: u8,
+    pub market: Pubkey,
+    pub votes: Vec<PriceVote>,
+    pub bump: u8,
+    pub ranges: Vec<RangeVote>,
+    /// Head-to-head markets only
+    pub rival_votes: Vec<PriceVote>,
+}
+
+impl SettlementVotes {
+    pub const LEN: usize = 8 + 1
+        + 32
+        + 4 + MAX_SETTLERS * PriceVote::LEN
+        + 1
+        + 4 + MAX_SETTLERS * RangeVote::LEN
+        + 4 + MAX_SETTLERS * PriceVote::LEN;
+
+    /// Records `price`, `high`, `low` and `rival_price` for `settler`,
+    /// replacing their earlier vote.
+    pub fn record(&mut self, settler: Pubkey, price: u64, high: u64, low: u64, rival_price: u64) {
+        match self.votes.iter_mut().find(|v| v.settler == settler) {
+            Some(vote) => vote.price = price,
+            None => self.votes.push(PriceVote { settler, price }),
+        }
+        match self.ranges.iter_mut().find(|v| v.settler == settler) {
+            Some(vote) => {
+                vote.high = high;
+                vote.low = low;
+            }
+            None => self.ranges.push(RangeVote { settler, high, low }),
+        }
+        match self.rival_votes.iter_mut().find(|v| v.settler == settler) {
+            Some(vote) => vote.price = rival_price,
+            None => self.rival_votes.push(PriceVote { settler, price: rival_price }),
+        }
+    }
+
+    /// Agreed rival close price among votes from current members of `set`.
+    pub fn quorum_rival_price(&self, set: &SettlerSet) -> Option<u64> {
+        let prices: Vec<u64> = self
+            .rival_votes
+            .iter()
+            .filter(|v| set.contains(&v.settler))
+            .map(|v| v.price)
+            .collect();
+        quorum_price(&prices, set.threshold, set.tolerance_bps)
+    }
+
+    /// Agreed high and low among votes from current members of `set`.
+    /// Both are agreed on separately, so there is none if they cross.
+    pub fn quorum_range(&self, set: &SettlerSet) -> Option<(u64, u64)> {
+        let members = || self.ranges.iter().filter(|v| set.contains(&v.settler));
+        let highs: Vec<u64> = members().map(|v| v.high).collect();
+        let lows: Vec<u64> = members().map(|v| v.low).collect();
+
+        let high = quorum_price(&highs, set.threshold, set.tolerance_bps)?;
+        let low = quorum_price(&lows, set.threshold, set.tolerance_bps)?;
+        (low <= high).then_some((high, low))
+    }
+
+    /// Settler who opened, and paid the rent of, this account.
+    pub fn rent_payer(&self) -> Option<Pubkey> {
+        self.votes.first().map(|v| v.settler)
+    }
+
+    /// Agreed price among votes from current members of `set`.
+    pub fn quorum_price(&self, set: &SettlerSet) -> Option<u64> {
+        let prices: Vec<u64> = self
+            .votes
+            .iter()
+            .filter(|v| set.contains(&v.settler))
+            .map(|v| v.price)
+            .collect();
+        quorum_price(&prices, set.threshold, set.tolerance_bps)
+    }
 }
 
 // ====================================
@@ -74,4 +1011,840 @@ impl TreasuryAccount {
 pub enum BetSide {
     Green,
     Red,
+    /// Range markets only
+    Bucket(u8),
+}
+
+impl BetSide {
+    /// Position of this side's pool in MarketAccount::pools_weighted.
+    pub fn index(&self) -> usize {
+        match self {
+            BetSide::Green => 0,
+            BetSide::Red => 1,
+            BetSide::Bucket(i) => *i as usize,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum MarketOutcome {
+    Pending,
+    Green,
+    Red,
+    /// Flat candle or one-sided market: every bet is refunded
+    Void,
+    /// Range markets only: the bucket the close price fell in
+    Bucket(u8),
+}
+
+
+// ====================================
+// PAYOUT MATH
+// ====================================
+
+/// Outcome of a settled market. A close exactly at the strike (the open
+/// price unless the market has its own), or a market where either side
+/// has no real stake, is void and every bettor is refunded.
+pub fn resolve_outcome(
+    strike_price: u64,
+    close_price: u64,
+    green_pool_weighted: u64,
+    red_pool_weighted: u64,
+    virtual_liquidity: u64,
+) -> MarketOutcome {
+    let green_stake = green_pool_weighted.saturating_sub(virtual_liquidity);
+    let red_stake = red_pool_weighted.saturating_sub(virtual_liquidity);
+
+    if close_price == strike_price || green_stake == 0 || red_stake == 0 {
+        MarketOutcome::Void
+    } else if close_price > strike_price {
+        MarketOutcome::Green
+    } else {
+        MarketOutcome::Red
+    }
+}
+
+/// Outcome of a settled volatility market: Green when the candle's range
+/// exceeds `threshold_bps` of the open, Red otherwise. Void when either
+/// side has no real stake.
+pub fn resolve_volatility_outcome(
+    open_price: u64,
+    high_price: u64,
+    low_price: u64,
+    threshold_bps: u32,
+    green_pool_weighted: u64,
+    red_pool_weighted: u64,
+    virtual_liquidity: u64,
+) -> MarketOutcome {
+    let green_stake = green_pool_weighted.saturating_sub(virtual_liquidity);
+    let red_stake = red_pool_weighted.saturating_sub(virtual_liquidity);
+    let range = high_price.saturating_sub(low_price) as u128 * 10_000;
+
+    if green_stake == 0 || red_stake == 0 {
+        MarketOutcome::Void
+    } else if range > open_price as u128 * threshold_bps as u128 {
+        MarketOutcome::Green
+    } else {
+        MarketOutcome::Red
+    }
+}
+
+/// Outcome of a settled head-to-head market: Green when `asset` returned
+/// more than the rival from open to close, Red when it returned less. Void
+/// on equal returns or when either side has no real stake.
+pub fn resolve_head_to_head_outcome(
+    open_price: u64,
+    close_price: u64,
+    rival_open_price: u64,
+    rival_close_price: u64,
+    green_pool_weighted: u64,
+    red_pool_weighted: u64,
+    virtual_liquidity: u64,
+) -> MarketOutcome {
+    let green_stake = green_pool_weighted.saturating_sub(virtual_liquidity);
+    let red_stake = red_pool_weighted.saturating_sub(virtual_liquidity);
+
+    // close / open against rival_close / rival_open, cross-multiplied
+    let growth = close_price as u128 * rival_open_price as u128;
+    let rival_growth = rival_close_price as u128 * open_price as u128;
+
+    if growth == rival_growth || green_stake == 0 || red_stake == 0 {
+        MarketOutcome::Void
+    } else if growth > rival_growth {
+        MarketOutcome::Green
+    } else {
+        MarketOutcome::Red
+    }
+}
+
+/// Bucket of a range market that a move from `strike_price` to
+/// `close_price` falls in: the number of `bounds` (bps) it reaches.
+pub fn bucket_for(strike_price: u64, close_price: u64, bounds: &[i32]) -> usize {
+    let close = close_price as i128 * 10_000;
+
+    bounds
+        .iter()
+        .filter(|&&bound| close >= strike_price as i128 * (10_000 + bound as i128))
+        .count()
+}
+
+/// Outcome of a settled range market. Void when the winning bucket, or
+/// every other bucket, has no real stake.
+pub fn resolve_range_outcome(
+    strike_price: u64,
+    close_price: u64,
+    bounds: &[i32],
+    pools_weighted: &[u64],
+    virtual_liquidity: u64,
+) -> MarketOutcome {
+    let bucket = bucket_for(strike_price, close_price, bounds);
+    let (winning, losing) = split_pools(pools_weighted, bucket, virtual_liquidity);
+
+    if winning == 0 || losing == 0 {
+        MarketOutcome::Void
+    } else {
+        MarketOutcome::Bucket(bucket as u8)
+    }
+}
+
+/// Real stake of the winning pool and of every other pool combined, i.e.
+/// net of virtual liquidity, as parimutuel_payout expects them.
+pub fn split_pools(pools_weighted: &[u64], winner: usize, virtual_liquidity: u64) -> (u64, u64) {
+    let mut winning = 0;
+    let mut losing: u64 = 0;
+
+    for (i, pool) in pools_weighted.iter().enumerate() {
+        let stake = pool.saturating_sub(virtual_liquidity);
+        if i == winner {
+            winning = stake;
+        } else {
+            losing = losing.saturating_add(stake);
+        }
+    }
+
+    (winning, losing)
+}
+
+/// Protocol fee owed on `winnings` (payout minus the original stake).
+pub fn protocol_fee(winnings: u64, fee_bps: u16) -> u64 {
+    // fee_bps <= 10_000, so the result always fits back into a u64
+    (winnings as u128 * fee_bps as u128 / 10_000) as u64
+}
+
+/// Parimutuel payout for a winning bet.
+///
+/// The bettor always gets their full `amount` back, plus a share of the
+/// losing pool proportional to their time-weighted `effective_stake`.
+/// Both pool totals are expected net of virtual liquidity. Shared with the
+/// backend so off-chain payout estimates match the program exactly.
+pub fn parimutuel_payout(
+    amount: u64,
+    effective_stake: u64,
+    total_winning_weighted: u64,
+    total_losing_weighted: u64,
+) -> Option<u64> {
+    if total_winning_weighted == 0 {
+        return Some(amount);
+    }
+
+    let share = (effective_stake as u128)
+        .checked_mul(total_losing_weighted as u128)?
+        .checked_div(total_winning_weighted as u128)?;
+
+    amount.checked_add(u64::try_from(share).ok()?)
+}
+
+/// Close price agreed by at least `threshold` votes, if any.
+///
+/// Votes agree when they are within `tolerance_bps` of the lowest price in
+/// the group. The first (lowest) group to reach the threshold wins and its
+/// median is used, so the result does not depend on vote order.
+pub fn quorum_price(prices: &[u64], threshold: u8, tolerance_bps: u16) -> Option<u64> {
+    let mut sorted = prices.to_vec();
+    sorted.sort_unstable();
+
+    for (i, &low) in sorted.iter().enumerate() {
+        let max_spread = (low as u128 * tolerance_bps as u128) / 10_000;
+        let group: Vec<u64> = sorted[i..]
+            .iter()
+            .copied()
+            .take_while(|&p| (p - low) as u128 <= max_spread)
+            .collect();
+
+        if group.len() >= threshold as usize {
+            return Some(group[group.len() / 2]);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serialized size of `account`, discriminator included.
+    fn serialized_len<T: AccountSerialize>(account: &T) -> usize {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.len()
+    }
+
+    /// Every variable-length field at its maximum; only for LEN.
+    fn largest_market() -> MarketAccount {
+        MarketAccount {
+            version: ACCOUNT_VERSION,
+            asset: "X".repeat(MAX_ASSET_LEN),
+            market_id: u64::MAX,
+            start_time: 0,
+            end_time: 0,
+            lock_time: 0,
+            open_price: 0,
+            close_price: 0,
+            green_pool_weighted: 0,
+            red_pool_weighted: 0,
+            virtual_liquidity: 0,
+            settled: true,
+            outcome: MarketOutcome::Bucket(0),
+            mint: Pubkey::default(),
+            decimals: 0,
+            open_bets: 0,
+            weight_schedule: vec![WeightTier { until_offset: 0, weight: 0 }; MAX_WEIGHT_TIERS],
+            final_weight: 0,
+            min_bet: 0,
+            max_bet: 0,
+            paused: false,
+            fee_bps: 0,
+            fees_accrued: 0,
+            finalizes_at: 0,
+            green_total: 0,
+            red_total: 0,
+            green_bettors: 0,
+            red_bettors: 0,
+            total_claimed: 0,
+            exit_penalty_bps: 0,
+            bucket_bounds: vec![0; MAX_BUCKETS - 1],
+            bucket_pools_weighted: vec![0; MAX_BUCKETS],
+            bucket_totals: vec![0; MAX_BUCKETS],
+            bucket_bettors: vec![0; MAX_BUCKETS],
+            strike_price: 0,
+            volatility_threshold_bps: 0,
+            high_price: 0,
+            low_price: 0,
+            rival_asset: "X".repeat(MAX_ASSET_LEN),
+            rival_open_price: 0,
+            rival_close_price: 0,
+        }
+    }
+
+    /// An open 4h Green/Red market on the default params, opened at 10_000.
+    fn green_red_market() -> MarketAccount {
+        let params = MarketParams::default();
+
+        MarketAccount {
+            version: ACCOUNT_VERSION,
+            asset: "BTC/USDT".to_string(),
+            market_id: 1,
+            start_time: 0,
+            end_time: 14_400,
+            lock_time: 14_400 - params.lock_offset,
+            open_price: 10_000,
+            close_price: 0,
+            green_pool_weighted: params.virtual_liquidity,
+            red_pool_weighted: params.virtual_liquidity,
+            virtual_liquidity: params.virtual_liquidity,
+            settled: false,
+            outcome: MarketOutcome::Pending,
+            mint: Pubkey::default(),
+            decimals: NATIVE_SOL_DECIMALS,
+            open_bets: 0,
+            weight_schedule: params.weight_schedule,
+            final_weight: params.final_weight,
+            min_bet: params.min_bet,
+            max_bet: params.max_bet,
+            paused: false,
+            fee_bps: 0,
+            fees_accrued: 0,
+            finalizes_at: 0,
+            green_total: 0,
+            red_total: 0,
+            green_bettors: 0,
+            red_bettors: 0,
+            total_claimed: 0,
+            exit_penalty_bps: 0,
+            bucket_bounds: Vec::new(),
+            bucket_pools_weighted: Vec::new(),
+            bucket_totals: Vec::new(),
+            bucket_bettors: Vec::new(),
+            strike_price: 10_000,
+            volatility_threshold_bps: 0,
+            high_price: 0,
+            low_price: 0,
+            rival_asset: String::new(),
+            rival_open_price: 0,
+            rival_close_price: 0,
+        }
+    }
+
+    /// Places `amount` on `side` for `user` at `now`, on top of `bet`.
+    fn bet_on(
+        market: &mut MarketAccount,
+        bet: &mut UserBetAccount,
+        side: BetSide,
+        amount: u64,
+        now: i64,
+    ) {
+        let user = Pubkey::new_unique();
+        market
+            .record_bet(bet, user, Pubkey::default(), side, amount, now)
+            .unwrap();
+    }
+
+    fn empty_bet() -> UserBetAccount {
+        UserBetAccount {
+            version: ACCOUNT_VERSION,
+            user: Pubkey::default(),
+            market: Pubkey::default(),
+            side: BetSide::Green,
+            amount: 0,
+            weight: 0,
+            effective_stake: 0,
+            claimed: false,
+        }
+    }
+
+    #[test]
+    fn len_matches_largest_serialized_market() {
+        assert_eq!(serialized_len(&largest_market()), MarketAccount::LEN);
+    }
+
+    #[test]
+    fn len_matches_serialized_accounts() {
+        let key = Pubkey::default();
+
+        let bet = UserBetAccount {
+            version: ACCOUNT_VERSION,
+            user: key,
+            market: key,
+            side: BetSide::Bucket(0),
+            amount: 0,
+            weight: 0,
+            effective_stake: 0,
+            claimed: false,
+        };
+        let treasury = TreasuryAccount {
+            version: ACCOUNT_VERSION,
+            bump: 0,
+            pending_amount: 0,
+            pending_recipient: key,
+            pending_executable_at: 0,
+            legacy_escrow: 0,
+        };
+        let config = ConfigAccount {
+            version: ACCOUNT_VERSION,
+            admin: key,
+            settler: key,
+            bump: 0,
+            paused: false,
+            fee_bps: 0,
+            dispute_period: 0,
+            resolver: key,
+            exit_penalty_bps: 0,
+        };
+        let settlers = SettlerSet {
+            version: ACCOUNT_VERSION,
+            settlers: vec![key; MAX_SETTLERS],
+            threshold: 0,
+            tolerance_bps: 0,
+            bump: 0,
+        };
+        let asset = AssetConfig {
+            version: ACCOUNT_VERSION,
+            symbol: "X".repeat(MAX_ASSET_LEN),
+            price_decimals: 0,
+            price_feed: key,
+            enabled: true,
+            bump: 0,
+        };
+        let votes = SettlementVotes {
+            version: ACCOUNT_VERSION,
+            market: key,
+            votes: vec![PriceVote { settler: key, price: 0 }; MAX_SETTLERS],
+            bump: 0,
+            ranges: vec![RangeVote { settler: key, high: 0, low: 0 }; MAX_SETTLERS],
+            rival_votes: vec![PriceVote { settler: key, price: 0 }; MAX_SETTLERS],
+        };
+
+        assert_eq!(serialized_len(&bet), UserBetAccount::LEN);
+        assert_eq!(serialized_len(&treasury), TreasuryAccount::LEN);
+        assert_eq!(serialized_len(&config), ConfigAccount::LEN);
+        assert_eq!(serialized_len(&settlers), SettlerSet::LEN);
+        assert_eq!(serialized_len(&asset), AssetConfig::LEN);
+        assert_eq!(serialized_len(&votes), SettlementVotes::LEN);
+        assert_eq!(
+            serialized_len(&MarketVault { version: ACCOUNT_VERSION, bump: 0 }),
+            MarketVault::LEN
+        );
+        assert_eq!(
+            serialized_len(&FeeVaultAccount { version: ACCOUNT_VERSION, bump: 0 }),
+            FeeVaultAccount::LEN
+        );
+    }
+
+    #[test]
+    fn range_market_pays_the_close_bucket() {
+        // < -2%, -2..0%, 0..2%, > 2%
+        let mut market = green_red_market();
+        market.bucket_bounds = vec![-200, 0, 200];
+        market.bucket_pools_weighted = vec![100 + 500, 100, 100 + 1_000, 100 + 1_500];
+
+        assert_eq!(bucket_for(10_000, 9_700, &market.bucket_bounds), 0);
+        assert_eq!(bucket_for(10_000, 9_800, &market.bucket_bounds), 1);
+        assert_eq!(bucket_for(10_000, 10_000, &market.bucket_bounds), 2);
+        assert_eq!(bucket_for(10_000, 10_201, &market.bucket_bounds), 3);
+
+        market.close_price = 10_100;
+        market.outcome = market.resolve_outcome();
+        assert!(market.outcome == MarketOutcome::Bucket(2));
+
+        // Half the winning pool takes half of the other buckets' 2_000
+        assert_eq!(market.payout_for(&BetSide::Bucket(2), 500, 500).unwrap(), 1_500);
+        assert_eq!(market.payout_for(&BetSide::Bucket(3), 500, 500).unwrap(), 0);
+
+        // Nobody in the bucket the price closed in
+        market.close_price = 9_900;
+        assert!(market.resolve_outcome() == MarketOutcome::Void);
+
+        assert!(market.has_side(&BetSide::Bucket(3)));
+        assert!(!market.has_side(&BetSide::Bucket(4)));
+        assert!(!market.has_side(&BetSide::Green));
+    }
+
+    #[test]
+    fn strike_replaces_the_open_price() {
+        let mut market = green_red_market();
+        market.strike_price = 0;
+        market.green_pool_weighted = 100 + 1_000;
+        market.red_pool_weighted = 100 + 1_000;
+        market.close_price = 10_050;
+
+        assert!(market.resolve_outcome() == MarketOutcome::Green);
+
+        market.strike_price = 10_100;
+        assert!(market.resolve_outcome() == MarketOutcome::Red);
+
+        market.strike_price = 10_050;
+        assert!(market.resolve_outcome() == MarketOutcome::Void);
+    }
+
+    #[test]
+    fn volatility_market_compares_the_candle_range() {
+        let mut market = green_red_market();
+        market.volatility_threshold_bps = 300;
+        market.green_pool_weighted = 100 + 1_000;
+        market.red_pool_weighted = 100 + 1_000;
+
+        // 3% of the open is exactly the threshold: not exceeded
+        market.high_price = 10_200;
+        market.low_price = 9_900;
+        assert!(market.resolve_outcome() == MarketOutcome::Red);
+
+        market.low_price = 9_899;
+        assert!(market.resolve_outcome() == MarketOutcome::Green);
+    }
+
+    #[test]
+    fn volatility_market_refuses_a_missing_high_or_low() {
+        let mut market = green_red_market();
+        market.volatility_threshold_bps = 300;
+        market.green_pool_weighted = 100 + 1_000;
+        market.red_pool_weighted = 100 + 1_000;
+
+        assert!(market.settle(10_000, 0, 0, 0, 14_400, 3_600).is_err());
+        assert!(market.settle(10_000, 10_200, 0, 0, 14_400, 3_600).is_err());
+        assert!(!market.settled);
+
+        market
+            .settle(10_000, 10_200, 9_900, 0, 14_400, 3_600)
+            .unwrap();
+        assert!(market.resettle(10_000, 10_200, 0, 0, 14_400).is_err());
+        assert_eq!(market.low_price, 9_900);
+    }
+
+    #[test]
+    fn head_to_head_market_compares_returns() {
+        let mut market = green_red_market();
+        market.rival_asset = "ETH/USDT".to_string();
+        market.rival_open_price = 3_000;
+        market.open_price = 60_000;
+        market.strike_price = 60_000;
+        market.green_pool_weighted = 100 + 1_000;
+        market.red_pool_weighted = 100 + 1_000;
+
+        // +1% against +2%
+        market.close_price = 60_600;
+        market.rival_close_price = 3_060;
+        assert!(market.resolve_outcome() == MarketOutcome::Red);
+
+        // +1% against +0.5%
+        market.rival_close_price = 3_015;
+        assert!(market.resolve_outcome() == MarketOutcome::Green);
+
+        // +1% each
+        market.rival_close_price = 3_030;
+        assert!(market.resolve_outcome() == MarketOutcome::Void);
+    }
+
+    #[test]
+    fn winners_get_their_stake_back_plus_a_share_of_the_losers() {
+        let mut market = green_red_market();
+        let (mut a, mut b, mut c) = (empty_bet(), empty_bet(), empty_bet());
+
+        // Weighted 1_000 and 2_100 on Green, 2_000 on Red
+        bet_on(&mut market, &mut a, BetSide::Green, 1_000, 0);
+        bet_on(&mut market, &mut b, BetSide::Green, 3_000, 4_000);
+        bet_on(&mut market, &mut c, BetSide::Red, 2_000, 0);
+
+        market
+            .settle(10_500, 10_500, 10_500, 0, 14_400, 3_600)
+            .unwrap();
+        assert!(market.outcome == MarketOutcome::Green);
+        assert!(market.claim(&mut a, 17_999).is_err());
+
+        // 1_000 * 2_000 / 3_100 = 645 won
+        assert_eq!(market.claim(&mut a, 18_000).unwrap().payout, 1_645);
+        assert!(market.claim(&mut a, 18_000).is_err());
+        assert_eq!(market.claim(&mut c, 18_000).unwrap().payout, 0);
+        assert_eq!(market.claim(&mut b, 18_000).unwrap().payout, 4_354);
+
+        // Paid out no more than was staked
+        assert!(market.total_claimed <= 6_000);
+    }
+
+    #[test]
+    fn void_refunds_every_stake_without_a_fee() {
+        let mut market = green_red_market();
+        market.fee_bps = 100;
+        let (mut a, mut b) = (empty_bet(), empty_bet());
+        bet_on(&mut market, &mut a, BetSide::Green, 1_000, 0);
+        bet_on(&mut market, &mut b, BetSide::Red, 2_500, 5_000);
+
+        market.void(true, 6_000).unwrap();
+        assert!(market.void(true, 6_000).is_err());
+
+        let claimed = market.claim(&mut a, 6_000).unwrap();
+        assert_eq!((claimed.payout, claimed.fee), (1_000, 0));
+        let claimed = market.claim(&mut b, 6_000).unwrap();
+        assert_eq!((claimed.payout, claimed.fee), (2_500, 0));
+    }
+
+    #[test]
+    fn top_up_blends_the_weight_of_each_tier() {
+        let mut market = green_red_market();
+        let mut bet = empty_bet();
+
+        bet_on(&mut market, &mut bet, BetSide::Green, 1_000, 0);
+        bet_on(&mut market, &mut bet, BetSide::Green, 1_000, 4_000);
+
+        assert_eq!(
+            (bet.amount, bet.effective_stake, bet.weight),
+            (2_000, 1_700, 85)
+        );
+        assert_eq!(market.green_pool_weighted, 100 + 1_700);
+        assert_eq!(
+            (market.green_total, market.green_bettors, market.open_bets),
+            (2_000, 1, 1)
+        );
+
+        let user = Pubkey::new_unique();
+        let other_side = market.record_bet(&mut bet, user, user, BetSide::Red, 1_000, 4_000);
+        assert!(other_side.is_err());
+    }
+
+    #[test]
+    fn winners_pay_the_fee_on_their_winnings_only() {
+        let mut market = green_red_market();
+        market.fee_bps = 100;
+        let (mut a, mut b, mut c) = (empty_bet(), empty_bet(), empty_bet());
+        bet_on(&mut market, &mut a, BetSide::Green, 1_000, 0);
+        bet_on(&mut market, &mut b, BetSide::Green, 3_000, 4_000);
+        bet_on(&mut market, &mut c, BetSide::Red, 2_000, 0);
+        market
+            .settle(10_500, 10_500, 10_500, 0, 14_400, 3_600)
+            .unwrap();
+
+        // 645 won, 1% of it to the protocol
+        let claimed = market.claim(&mut a, 18_000).unwrap();
+        assert_eq!((claimed.payout, claimed.fee), (1_639, 6));
+        let claimed = market.claim(&mut c, 18_000).unwrap();
+        assert_eq!((claimed.payout, claimed.fee), (0, 0));
+        let claimed = market.claim(&mut b, 18_000).unwrap();
+        assert_eq!((claimed.payout, claimed.fee), (4_341, 13));
+
+        assert_eq!(market.total_claimed, 1_639 + 4_341);
+        assert_eq!(market.fees_accrued, 6 + 13);
+        assert!(market.total_claimed + market.fees_accrued <= 6_000);
+    }
+
+    #[test]
+    fn claiming_several_markets_pays_each_once() {
+        // claim_many runs these claims back to back; a market listed
+        // twice must fail rather than pay again
+        let mut markets = [green_red_market(), green_red_market()];
+        let mut bets = [empty_bet(), empty_bet()];
+        let mut total_payout = 0;
+
+        for (market, bet) in markets.iter_mut().zip(bets.iter_mut()) {
+            bet_on(market, bet, BetSide::Green, 1_000, 0);
+            bet_on(market, &mut empty_bet(), BetSide::Red, 500, 0);
+            market.settle(10_500, 10_500, 10_500, 0, 14_400, 0).unwrap();
+
+            total_payout += market.claim(bet, 14_400).unwrap().payout;
+        }
+
+        assert_eq!(total_payout, 2 * 1_500);
+        assert!(markets[0].claim(&mut bets[0], 14_400).is_err());
+    }
+
+    #[test]
+    fn resettle_moves_the_outcome_inside_the_window_only() {
+        let mut market = green_red_market();
+        let (mut a, mut b) = (empty_bet(), empty_bet());
+        bet_on(&mut market, &mut a, BetSide::Green, 1_000, 0);
+        bet_on(&mut market, &mut b, BetSide::Red, 1_000, 0);
+
+        market
+            .settle(10_500, 10_500, 10_500, 0, 14_400, 3_600)
+            .unwrap();
+        market.resettle(9_500, 9_500, 9_500, 0, 17_999).unwrap();
+        assert!(market.outcome == MarketOutcome::Red);
+        assert_eq!(market.finalizes_at, 18_000);
+
+        assert!(market.resettle(10_500, 10_500, 10_500, 0, 18_000).is_err());
+        assert_eq!(market.claim(&mut b, 18_000).unwrap().payout, 2_000);
+    }
+
+    #[test]
+    fn withdraw_refunds_the_stake_less_the_exit_penalty() {
+        let mut market = green_red_market();
+        market.exit_penalty_bps = 200;
+        let mut bet = empty_bet();
+        bet_on(&mut market, &mut bet, BetSide::Green, 1_000, 4_000);
+
+        let exit = market.withdraw(&bet, 5_000).unwrap();
+        assert_eq!((exit.refund, exit.penalty), (980, 20));
+        assert_eq!(market.green_pool_weighted, market.virtual_liquidity);
+        assert_eq!(
+            (market.green_total, market.green_bettors, market.open_bets),
+            (0, 0, 0)
+        );
+        assert_eq!(market.fees_accrued, 20);
+
+        let mut bet = empty_bet();
+        bet_on(&mut market, &mut bet, BetSide::Green, 1_000, 0);
+        assert!(market.withdraw(&bet, market.lock_time).is_err());
+    }
+
+    #[test]
+    fn oversized_stake_fails_without_touching_the_pools() {
+        let mut market = green_red_market();
+        market.max_bet = u64::MAX;
+        let key = Pubkey::new_unique();
+
+        // amount * weight no longer fits in a u64
+        let mut bet = empty_bet();
+        let res = market.record_bet(&mut bet, key, key, BetSide::Green, u64::MAX / 2, 0);
+        assert!(res.is_err());
+        assert_eq!(market.green_pool_weighted, market.virtual_liquidity);
+        assert_eq!(market.open_bets, 0);
+
+        // The largest stake that still fits is fine
+        let amount = u64::MAX / 100;
+        let stake = market.record_bet(&mut bet, key, key, BetSide::Green, amount, 0).unwrap();
+        assert_eq!(stake, amount);
+        assert_eq!(bet.weight, 100);
+    }
+
+    #[test]
+    fn legacy_escrow_is_what_claims_have_not_paid_out() {
+        let mut market = green_red_market();
+        market.green_total = 3_000;
+        market.red_total = 2_000;
+        assert_eq!(market.legacy_escrow().unwrap(), 5_000);
+
+        // A claim paid 4_000 to the bettor and 100 to the fee vault
+        market.total_claimed = 4_000;
+        market.fees_accrued = 100;
+        assert_eq!(market.legacy_escrow().unwrap(), 900);
+
+        market.total_claimed = 6_000;
+        assert_eq!(market.legacy_escrow().unwrap(), 0);
+    }
+
+    #[test]
+    fn withdrawal_leaves_legacy_escrow_in_the_treasury() {
+        let mut treasury = TreasuryAccount {
+            version: ACCOUNT_VERSION,
+            bump: 0,
+            pending_amount: 600,
+            pending_recipient: Pubkey::new_unique(),
+            pending_executable_at: 100,
+            legacy_escrow: 500,
+        };
+
+        assert!(treasury.take_withdrawal(1_000, 99).is_err());
+        assert!(treasury.take_withdrawal(1_000, 100).is_err());
+        assert_eq!(treasury.pending_amount, 600);
+
+        assert_eq!(treasury.take_withdrawal(1_100, 100).unwrap(), 600);
+        assert_eq!(treasury.pending_amount, 0);
+        assert!(treasury.take_withdrawal(1_100, 100).is_err());
+    }
+
+    #[test]
+    fn legacy_escrow_only_drops_to_what_is_still_owed() {
+        let mut treasury = TreasuryAccount {
+            version: ACCOUNT_VERSION,
+            bump: 0,
+            pending_amount: 0,
+            pending_recipient: Pubkey::default(),
+            pending_executable_at: 0,
+            legacy_escrow: 5_000,
+        };
+
+        // The reserve was the whole balance; two markets still owe 900 each
+        let mut market = green_red_market();
+        market.green_total = 3_000;
+        market.red_total = 2_000;
+        market.total_claimed = 4_000;
+        market.fees_accrued = 100;
+        let still_owed = 2 * market.legacy_escrow().unwrap();
+
+        assert_eq!(treasury.release_legacy_escrow(still_owed), 3_200);
+        assert_eq!(treasury.legacy_escrow, 1_800);
+
+        assert_eq!(treasury.release_legacy_escrow(2_500), 0);
+        assert_eq!(treasury.legacy_escrow, 1_800);
+    }
+
+    #[test]
+    fn resolver_voids_only_inside_the_dispute_window() {
+        let mut market = green_red_market();
+        assert!(market.void(false, 100).is_err());
+
+        // Bets on both sides, so settling does not void it already
+        market.green_pool_weighted += 1_000;
+        market.red_pool_weighted += 1_000;
+        market
+            .settle(10_500, 10_600, 9_900, 0, 14_400, 3_600)
+            .unwrap();
+        assert!(market.void(false, 18_000).is_err());
+        market.void(false, 17_999).unwrap();
+        assert!(market.outcome == MarketOutcome::Void);
+        assert_eq!(market.finalizes_at, 17_999);
+
+        // The admin may void an unsettled market
+        let mut market = green_red_market();
+        market.void(true, 100).unwrap();
+        assert!(market.is_final(100));
+    }
+
+    #[test]
+    fn validates_asset_symbols() {
+        assert!(is_valid_symbol("BTC/USDT"));
+        assert!(is_valid_symbol(&"A".repeat(MAX_ASSET_LEN)));
+
+        assert!(!is_valid_symbol(""));
+        assert!(!is_valid_symbol("btc/usdt"));
+        assert!(!is_valid_symbol("BTC USDT"));
+        assert!(!is_valid_symbol(&"A".repeat(MAX_ASSET_LEN + 1)));
+    }
+
+    #[test]
+    fn quorum_needs_threshold_votes_within_tolerance() {
+        // 10 bps of 6_400_000 = 6_400
+        assert_eq!(quorum_price(&[6_400_000, 6_403_000], 2, 10), Some(6_403_000));
+        assert_eq!(quorum_price(&[6_400_000, 6_410_000], 2, 10), None);
+        assert_eq!(quorum_price(&[6_400_000], 2, 10), None);
+    }
+
+    #[test]
+    fn quorum_ignores_outliers_and_vote_order() {
+        let a = quorum_price(&[7_000_000, 6_400_000, 6_401_000, 6_402_000], 3, 10);
+        let b = quorum_price(&[6_402_000, 6_401_000, 7_000_000, 6_400_000], 3, 10);
+
+        assert_eq!(a, Some(6_401_000));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn quorum_range_rejects_a_low_above_the_high() {
+        let settlers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let set = SettlerSet {
+            version: ACCOUNT_VERSION,
+            settlers: settlers.clone(),
+            threshold: 2,
+            tolerance_bps: 10,
+            bump: 0,
+        };
+        let mut votes = SettlementVotes {
+            version: ACCOUNT_VERSION,
+            market: Pubkey::new_unique(),
+            votes: Vec::new(),
+            bump: 0,
+            ranges: Vec::new(),
+            rival_votes: Vec::new(),
+        };
+
+        // Each vote is sound, but the agreed high is 10_000 and the agreed low 10_005
+        votes.record(settlers[0], 10_000, 10_000, 9_000, 0);
+        votes.record(settlers[1], 10_000, 10_000, 10_000, 0);
+        votes.record(settlers[2], 10_000, 10_050, 10_005, 0);
+        assert_eq!(votes.quorum_price(&set), Some(10_000));
+        assert_eq!(votes.quorum_range(&set), None);
+        assert_eq!(votes.rent_payer(), Some(settlers[0]));
+
+        votes.record(settlers[2], 10_000, 10_000, 9_995, 0);
+        assert_eq!(votes.quorum_range(&set), Some((10_000, 10_000)));
+    }
 }